@@ -0,0 +1,387 @@
+//! # inline-spirv-build
+//!
+//! Compile shader files in build scripts with the same compiler backends as
+//! `inline-spirv`. Compared to an `include_spirv!` per shader, the shaders are
+//! compiled in parallel, and only when they or the files they include change.
+//!
+//! In `build.rs`:
+//!
+//! ```ignore
+//! use inline_spirv_build::{Builder, ShaderStage, TargetEnv};
+//!
+//! fn main() {
+//!     Builder::new()
+//!         .target(TargetEnv::Vulkan1_2)
+//!         .shader("shaders/water.hlsl")
+//!         .stage(ShaderStage::Fragment)
+//!         .define("USE_COLOR", None)
+//!         .shader("shaders/sky.vert")
+//!         .compile();
+//! }
+//! ```
+//!
+//! And in your crate, include the generated module of `pub static` shader
+//! constants:
+//!
+//! ```ignore
+//! mod shaders {
+//!     include!(concat!(env!("OUT_DIR"), "/shaders.rs"));
+//! }
+//! let spv: &'static [u32] = shaders::WATER_HLSL;
+//! ```
+//!
+//! ## Shader Options
+//!
+//! Options set before the first [`shader`](Builder::shader) are the defaults
+//! of all the shaders; options set after a `shader` only apply to that shader.
+//! As with `include_spirv!`, the source language and the stage are inferred
+//! from the file extensions if they are not given, and paths are relative to
+//! the crate root.
+//!
+//! ## Generated Module
+//!
+//! Each shader is named after its file name in upper snake case, e.g.,
+//! `shaders/sky.vert` is `SKY_VERT`, unless it's named with
+//! [`name`](Builder::name). The module is written to `$OUT_DIR/shaders.rs`, or
+//! the file given with [`out_file`](Builder::out_file).
+//!
+//! ## Shader Manifest
+//!
+//! With the `manifest` feature, the shaders declared in a `shaders.toml`
+//! manifest (see `spirv_compile_core::manifest`) can be added with
+//! [`manifest`](Builder::manifest), and are named as in `shader_manifest!` of
+//! `inline-spirv`:
+//!
+//! ```ignore
+//! Builder::new()
+//!     .manifest("shaders/shaders.toml")
+//!     .compile();
+//! ```
+//!
+//! ## Change Tracking
+//!
+//! `cargo:rerun-if-changed` is emitted for every shader file, the resource
+//! limit files and all the included files, so the build script only reruns if
+//! any of them changes. Compilation warnings are reported with
+//! `cargo:warning`.
+//!
+//! For build tools outside Cargo, [`depfile`](Builder::depfile) writes a
+//! Makefile-style depfile of the generated module depending on all those
+//! files, and [`include_graph`](Builder::include_graph) writes the include
+//! graph of all the shaders in JSON.
+use std::fmt::Write;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Mutex, PoisonError};
+pub use spirv_compile_core::{CompilationFeedback, CompileOptions, Compiler,
+    DepGraph, OptimizationLevel, ShaderStage, SourceLanguage, TargetEnv};
+
+struct Shader {
+    path: PathBuf,
+    name: Option<String>,
+    opts: CompileOptions,
+}
+
+/// Builder of a batch of shaders compiled in a build script.
+pub struct Builder {
+    defaults: CompileOptions,
+    shaders: Vec<Shader>,
+    #[cfg(feature = "manifest")]
+    manifests: Vec<PathBuf>,
+    out_dir: Option<PathBuf>,
+    out_file: PathBuf,
+    depfile: Option<PathBuf>,
+    include_graph: Option<PathBuf>,
+    jobs: Option<usize>,
+    cargo_metadata: bool,
+}
+impl Default for Builder {
+    fn default() -> Self {
+        Builder {
+            defaults: CompileOptions::new(),
+            shaders: Vec::new(),
+            #[cfg(feature = "manifest")]
+            manifests: Vec::new(),
+            out_dir: None,
+            out_file: PathBuf::from("shaders.rs"),
+            depfile: None,
+            include_graph: None,
+            jobs: None,
+            cargo_metadata: true,
+        }
+    }
+}
+impl Builder {
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// Options of the last added shader, or the defaults if no shader is
+    /// added yet.
+    fn map_opts<F: FnOnce(CompileOptions) -> CompileOptions>(mut self, f: F) -> Self {
+        let opts = match self.shaders.last_mut() {
+            Some(shader) => &mut shader.opts,
+            None => &mut self.defaults,
+        };
+        *opts = f(std::mem::take(opts));
+        self
+    }
+
+    /// Add the shader file at `path`. Following options only apply to this
+    /// shader.
+    pub fn shader<P: Into<PathBuf>>(mut self, path: P) -> Self {
+        let shader = Shader {
+            path: path.into(),
+            name: None,
+            opts: self.defaults.clone(),
+        };
+        self.shaders.push(shader);
+        self
+    }
+    /// Add all the shaders declared in the manifest at `path`. Options of the
+    /// builder don't apply to them; they are declared in the manifest instead.
+    #[cfg(feature = "manifest")]
+    pub fn manifest<P: Into<PathBuf>>(mut self, path: P) -> Self {
+        self.manifests.push(path.into());
+        self
+    }
+    /// Name of the constant of the last added shader in the generated module.
+    pub fn name<S: Into<String>>(mut self, name: S) -> Self {
+        if let Some(shader) = self.shaders.last_mut() {
+            shader.name = Some(name.into());
+        }
+        self
+    }
+    /// Replace the options altogether, e.g., with options shared with
+    /// `jit-spirv` at runtime.
+    pub fn options(self, opts: CompileOptions) -> Self {
+        self.map_opts(|_| opts)
+    }
+    pub fn lang(self, lang: SourceLanguage) -> Self {
+        self.map_opts(|x| x.lang(lang))
+    }
+    pub fn stage(self, stage: ShaderStage) -> Self {
+        self.map_opts(|x| x.stage(stage))
+    }
+    pub fn entry<S: Into<String>>(self, entry: S) -> Self {
+        self.map_opts(|x| x.entry(entry))
+    }
+    pub fn define(self, name: &str, value: Option<&str>) -> Self {
+        self.map_opts(|x| x.define(name, value))
+    }
+    pub fn include_dir<P: Into<PathBuf>>(self, dir: P) -> Self {
+        self.map_opts(|x| x.include_dir(dir))
+    }
+    pub fn target(self, target: TargetEnv) -> Self {
+        self.map_opts(|x| x.target(target))
+    }
+    pub fn optimization(self, optim_lv: OptimizationLevel) -> Self {
+        self.map_opts(|x| x.optimization(optim_lv))
+    }
+    pub fn debug(self, debug: bool) -> Self {
+        self.map_opts(|x| x.debug(debug))
+    }
+    pub fn auto_bind(self, auto_bind: bool) -> Self {
+        self.map_opts(|x| x.auto_bind(auto_bind))
+    }
+
+    /// Directory of the generated module. `OUT_DIR` by default.
+    pub fn out_dir<P: Into<PathBuf>>(mut self, out_dir: P) -> Self {
+        self.out_dir = Some(out_dir.into());
+        self
+    }
+    /// Path to the generated module, relative to the output directory.
+    /// `shaders.rs` by default.
+    pub fn out_file<P: Into<PathBuf>>(mut self, out_file: P) -> Self {
+        self.out_file = out_file.into();
+        self
+    }
+    /// Path to a Makefile-style depfile of the generated module, relative to
+    /// the output directory.
+    pub fn depfile<P: Into<PathBuf>>(mut self, depfile: P) -> Self {
+        self.depfile = Some(depfile.into());
+        self
+    }
+    /// Path to the include graph of all the shaders in JSON, relative to the
+    /// output directory.
+    pub fn include_graph<P: Into<PathBuf>>(mut self, include_graph: P) -> Self {
+        self.include_graph = Some(include_graph.into());
+        self
+    }
+    /// Number of shaders compiled in parallel. By default it's `NUM_JOBS` set
+    /// by Cargo, or the available parallelism.
+    pub fn jobs(mut self, jobs: usize) -> Self {
+        self.jobs = Some(jobs);
+        self
+    }
+    /// Whether to print `cargo:rerun-if-changed` and `cargo:warning`. Enabled
+    /// by default.
+    pub fn cargo_metadata(mut self, cargo_metadata: bool) -> Self {
+        self.cargo_metadata = cargo_metadata;
+        self
+    }
+
+    #[cfg(feature = "manifest")]
+    fn load_manifests(&mut self) -> Result<(), String> {
+        for path in self.manifests.iter() {
+            if self.cargo_metadata {
+                println!("cargo:rerun-if-changed={}", path.display());
+            }
+            let manifest = spirv_compile_core::manifest::load_manifest(path)?;
+            let shaders = manifest.into_iter()
+                .map(|x| Shader { path: x.path, name: Some(x.name), opts: x.opts });
+            self.shaders.extend(shaders);
+        }
+        Ok(())
+    }
+    #[cfg(not(feature = "manifest"))]
+    fn load_manifests(&mut self) -> Result<(), String> {
+        Ok(())
+    }
+
+    fn nworker(&self) -> usize {
+        let nworker = self.jobs
+            .or_else(|| std::env::var("NUM_JOBS").ok()?.parse().ok())
+            .or_else(|| Some(std::thread::available_parallelism().ok()?.get()))
+            .unwrap_or(1);
+        nworker.clamp(1, self.shaders.len().max(1))
+    }
+
+    fn compile_all(&self) -> Result<Vec<Result<CompilationFeedback, String>>, String> {
+        let compiler = Compiler::new()?;
+        let next = AtomicUsize::new(0);
+        let results = Mutex::new(Vec::new());
+        std::thread::scope(|s| {
+            for _ in 0..self.nworker() {
+                s.spawn(|| loop {
+                    let i = next.fetch_add(1, Ordering::Relaxed);
+                    let shader = match self.shaders.get(i) {
+                        Some(x) => x,
+                        None => break,
+                    };
+                    let result = compiler.compile_file(&shader.path, &shader.opts);
+                    results.lock()
+                        .unwrap_or_else(PoisonError::into_inner)
+                        .push((i, result));
+                });
+            }
+        });
+        let mut results = results.into_inner()
+            .unwrap_or_else(PoisonError::into_inner);
+        results.sort_by_key(|(i, _)| *i);
+        Ok(results.into_iter().map(|(_, result)| result).collect())
+    }
+
+    /// Compile all the shaders and generate the module, panicking with the
+    /// diagnostics if any shader fails to compile.
+    pub fn compile(self) {
+        if let Err(e) = self.try_compile() {
+            panic!("\n{}\n", e);
+        }
+    }
+    /// Compile all the shaders and generate the module. Returns the path to the
+    /// generated module. If any shader fails to compile, the error lists the
+    /// diagnostics of all the failed shaders.
+    pub fn try_compile(mut self) -> Result<PathBuf, String> {
+        let out_dir = match &self.out_dir {
+            Some(x) => x.clone(),
+            None => std::env::var_os("OUT_DIR")
+                .map(PathBuf::from)
+                .ok_or("`OUT_DIR` is not set; `inline-spirv-build` is meant to be used in build scripts")?,
+        };
+        self.load_manifests()?;
+        let names = self.shaders.iter()
+            .map(|shader| shader.name.clone().unwrap_or_else(|| const_name(&shader.path)))
+            .collect::<Vec<_>>();
+        for (i, name) in names.iter().enumerate() {
+            if names[..i].contains(name) {
+                return Err(format!("more than one shader is named `{}`; name them \
+                    apart with `Builder::name`", name));
+            }
+        }
+
+        if self.cargo_metadata {
+            for shader in self.shaders.iter() {
+                println!("cargo:rerun-if-changed={}", shader.path.display());
+            }
+        }
+        let results = self.compile_all()?;
+
+        let mut errors = Vec::new();
+        let mut graph = DepGraph::new();
+        let mut module = "// Generated by `inline-spirv-build`. Do not edit.\n".to_owned();
+        for ((shader, name), result) in self.shaders.iter().zip(names.iter()).zip(results) {
+            let feedback = match result {
+                Ok(x) => x,
+                Err(e) => {
+                    errors.push(format!("cannot compile \"{}\":\n  {}",
+                        shader.path.display(), e.trim_end().replace('\n', "\n  ")));
+                    continue;
+                },
+            };
+            if self.cargo_metadata {
+                for dep_path in feedback.dep_paths.iter() {
+                    if Path::new(dep_path) != shader.path {
+                        println!("cargo:rerun-if-changed={}", dep_path);
+                    }
+                }
+                for warning in feedback.warnings.iter() {
+                    for line in warning.lines() {
+                        println!("cargo:warning={}: {}", shader.path.display(), line);
+                    }
+                }
+            }
+            graph.add(&feedback);
+            write_const(&mut module, name, &shader.path, &feedback);
+        }
+        if !errors.is_empty() {
+            return Err(errors.join("\n"));
+        }
+
+        let out_path = out_dir.join(&self.out_file);
+        write_file(&out_path, module)?;
+        if let Some(depfile) = &self.depfile {
+            write_file(&out_dir.join(depfile), graph.to_depfile(&out_path.to_string_lossy()))?;
+        }
+        if let Some(include_graph) = &self.include_graph {
+            write_file(&out_dir.join(include_graph), graph.to_json())?;
+        }
+        Ok(out_path)
+    }
+}
+
+fn write_file(path: &Path, content: String) -> Result<(), String> {
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir)
+            .map_err(|e| format!("cannot create directory \"{}\": {}", dir.display(), e))?;
+    }
+    std::fs::write(path, content)
+        .map_err(|e| format!("cannot write to \"{}\": {}", path.display(), e))
+}
+
+/// Name of the constant of a shader file, e.g., `SKY_VERT` for `sky.vert`.
+fn const_name(path: &Path) -> String {
+    let file_name = path.file_name()
+        .map(|x| x.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    let mut name = file_name.chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c.to_ascii_uppercase() } else { '_' })
+        .collect::<String>();
+    if !name.starts_with(|c: char| c.is_ascii_alphabetic()) {
+        name.insert(0, '_');
+    }
+    name
+}
+
+fn write_const(out: &mut String, name: &str, path: &Path, feedback: &CompilationFeedback) {
+    writeln!(out).unwrap();
+    writeln!(out, "/// Entry point `{}` of `{}` in SPIR-V.", feedback.entry, path.display()).unwrap();
+    writeln!(out, "pub static {}: &[u32] = &[", name).unwrap();
+    for words in feedback.spv.chunks(8) {
+        let words = words.iter()
+            .map(|x| format!("0x{:08x},", x))
+            .collect::<Vec<_>>();
+        writeln!(out, "    {}", words.join(" ")).unwrap();
+    }
+    writeln!(out, "];").unwrap();
+}
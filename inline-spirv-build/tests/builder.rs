@@ -0,0 +1,111 @@
+use std::path::PathBuf;
+use inline_spirv_build::Builder;
+
+fn test_dir(name: &str) -> PathBuf {
+    let dir = PathBuf::from(env!("CARGO_TARGET_TMPDIR")).join(name);
+    std::fs::create_dir_all(&dir).unwrap();
+    dir
+}
+
+#[test]
+fn shader_names_must_be_unique() {
+    let dir = test_dir("shader_names_must_be_unique");
+    let e = Builder::new()
+        .out_dir(&dir)
+        .cargo_metadata(false)
+        .shader("a/sky.vert")
+        .shader("b/sky.vert")
+        .try_compile()
+        .unwrap_err();
+    assert!(e.contains("`SKY_VERT`"), "{}", e);
+}
+
+#[test]
+fn every_failed_shader_is_reported() {
+    let dir = test_dir("every_failed_shader_is_reported");
+    let e = Builder::new()
+        .out_dir(&dir)
+        .cargo_metadata(false)
+        .shader(dir.join("missing.vert"))
+        .shader(dir.join("missing.frag"))
+        .name("MISSING_FRAG_2")
+        .try_compile()
+        .unwrap_err();
+    assert!(e.contains("missing.vert"), "{}", e);
+    assert!(e.contains("missing.frag"), "{}", e);
+    assert!(!dir.join("shaders.rs").exists());
+}
+
+#[cfg(feature = "wgsl")]
+#[test]
+fn module_of_shader_constants_is_generated() {
+    use inline_spirv_build::ShaderStage;
+    let dir = test_dir("module_of_shader_constants_is_generated");
+    let src = r#"
+        @fragment
+        fn fs_main() -> @location(0) vec4<f32> { return vec4<f32>(1.0); }
+        @vertex
+        fn vs_main() -> @builtin(position) vec4<f32> { return vec4<f32>(0.0); }
+    "#;
+    std::fs::write(dir.join("quad.wgsl"), src).unwrap();
+    let out_path = Builder::new()
+        .out_dir(&dir)
+        .out_file("gen/quad.rs")
+        .depfile("gen/quad.d")
+        .cargo_metadata(false)
+        .jobs(2)
+        .shader(dir.join("quad.wgsl"))
+        .stage(ShaderStage::Fragment)
+        .entry("fs_main")
+        .shader(dir.join("quad.wgsl"))
+        .name("QUAD_VERT")
+        .stage(ShaderStage::Vertex)
+        .entry("vs_main")
+        .try_compile()
+        .unwrap();
+    assert_eq!(out_path, dir.join("gen/quad.rs"));
+
+    let module = std::fs::read_to_string(&out_path).unwrap();
+    let frag = module.find("pub static QUAD_WGSL: &[u32] = &[\n    0x07230203,").unwrap();
+    let vert = module.find("pub static QUAD_VERT: &[u32] = &[\n    0x07230203,").unwrap();
+    assert!(frag < vert, "{}", module);
+    assert!(module.contains("/// Entry point `fs_main` of"), "{}", module);
+    assert!(module.contains("/// Entry point `vs_main` of"), "{}", module);
+    // Both shaders depend on the same file.
+    let depfile = std::fs::read_to_string(dir.join("gen/quad.d")).unwrap();
+    assert_eq!(depfile.matches("quad.wgsl").count(), 1, "{}", depfile);
+    assert!(depfile.starts_with(&format!("{}:", out_path.display())), "{}", depfile);
+}
+
+#[cfg(all(feature = "manifest", feature = "wgsl"))]
+#[test]
+fn manifest_shaders_are_generated() {
+    let dir = test_dir("manifest_shaders_are_generated");
+    std::fs::write(dir.join("quad.wgsl"), r#"
+        @fragment
+        fn fs_main() -> @location(0) vec4<f32> { return vec4<f32>(1.0); }
+    "#).unwrap();
+    std::fs::write(dir.join("shaders.toml"), r#"
+        [shaders.quad]
+        path = "quad.wgsl"
+        stage = "frag"
+        entry = "fs_main"
+    "#).unwrap();
+    let out_path = Builder::new()
+        .out_dir(&dir)
+        .cargo_metadata(false)
+        .manifest(dir.join("shaders.toml"))
+        .try_compile()
+        .unwrap();
+    let module = std::fs::read_to_string(out_path).unwrap();
+    assert!(module.contains("pub static QUAD: &[u32] = &[\n    0x07230203,"), "{}", module);
+
+    std::fs::write(dir.join("shaders.toml"), "[shaders.quad]\nstage = \"frag\"\n").unwrap();
+    let e = Builder::new()
+        .out_dir(&dir)
+        .cargo_metadata(false)
+        .manifest(dir.join("shaders.toml"))
+        .try_compile()
+        .unwrap_err();
+    assert!(e.contains("shaders.toml:1:10: shader has no `path`"), "{}", e);
+}
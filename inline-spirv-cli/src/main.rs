@@ -0,0 +1,368 @@
+//! # inline-spirv-cli
+//!
+//! The `inline-spirv` command compiles a shader file to SPIR-V with the same
+//! options as the `inline-spirv` and `jit-spirv` macros, so what a macro
+//! invocation produced can be reproduced, or shader assets can be precompiled,
+//! without writing Rust:
+//!
+//! ```text
+//! inline-spirv assets/water.hlsl --stage frag --hlsl -D USE_COLOR -I assets/include --vulkan1_2 --max-perf
+//! ```
+//!
+//! Every macro option `x` is given as `--x`, and dashes can be used in place
+//! of underscores. Options with a value take it after `=` or in the next
+//! argument; values of the options taking a parenthesized list in the macros,
+//! e.g., `limit(...)`, are written as the content of the list:
+//!
+//! ```text
+//! inline-spirv water.frag --entry=main --glsl_version 450 --limit "MaxLights=8"
+//! ```
+//!
+//! Besides, `--stage <stage>` is short for a stage option like `--frag`, and
+//! definitions and include directories are given with `-D NAME[=VALUE]` and
+//! `-I <dir>` like in C compilers. The source language and the stage are
+//! inferred from the file extensions if they are not given. Options that only
+//! make sense in Rust code, e.g., `dump` and `cache`, are not accepted.
+//!
+//! ## Outputs
+//!
+//! - `-o <path>`: The SPIR-V binary, `<input>.spv` by default;
+//! - `--spvasm-out <path>`: The SPIR-V disassembly;
+//! - `--depfile <path>`: A Makefile-style depfile listing the source file and
+//!   all the files it includes, for build systems like Make and Ninja;
+//! - `--include-graph <path>`: The include graph in JSON, i.e., the files the
+//!   shader depends on and every resolved `#include`;
+//! - `--reflect-out <path>`: The entry points and the interface variables in
+//!   JSON.
+//!
+//! Diagnostics are printed to the standard error. The command exits with 2 if
+//! the command line is invalid, and with 1 if the shader fails to compile.
+mod reflect;
+
+use std::ffi::OsString;
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
+use proc_macro2::{TokenStream, TokenTree};
+use spirv_compile_core::syntax::{parse_compile_cfg, OPTION_KEYWORDS};
+use spirv_compile_core::{CompilationFeedback, CompileOptions, Compiler, DepGraph,
+    ShaderStage};
+use syn::parse::{ParseStream, Parser};
+
+const USAGE: &str = "\
+usage: inline-spirv <INPUT> [OPTIONS]
+
+Compile a shader file to SPIR-V with the options of the `inline-spirv` macros.
+
+outputs:
+    -o <path>               SPIR-V binary [default: <INPUT>.spv]
+    --spvasm-out <path>     SPIR-V disassembly
+    --depfile <path>        Makefile-style dependency file
+    --include-graph <path>  Include graph in JSON
+    --reflect-out <path>    Reflection in JSON
+
+options:
+    --stage <stage>         Shader stage, e.g., `vert`, `frag` or `comp`
+    -D <name>[=<value>]     Define a macro
+    -I <dir>                Add an include directory
+    --<option>[=<value>]    Any option of the macros, e.g., `--hlsl`,
+                            `--vulkan1_2`, `--max-perf` or `--entry=main`
+    -h, --help              Print this message
+";
+
+/// Options taking a string literal in the macros.
+const STR_OPTIONS: &[&str] = &["entry", "shader_model", "limits_file"];
+/// Options taking a literal or an identifier in the macros.
+const RAW_OPTIONS: &[&str] = &["glsl_version", "profile", "generator", "bound"];
+/// Options taking a parenthesized list in the macros.
+const LIST_OPTIONS: &[&str] = &[
+    "limit", "binding_base", "binding_base_for_stage",
+    "hlsl_explicit_binding", "hlsl_explicit_binding_for_stage",
+];
+/// Options that only make sense in Rust code. `I` and `D` are given with `-I`
+/// and `-D`.
+const UNSUPPORTED_OPTIONS: &[&str] = &[
+    "path", "I", "D", "include_dirs", "defines", "dump", "out_version",
+    "cache", "resolver", "crate",
+];
+
+/// A command line error, optionally pointing at the argument it's about.
+struct UsageError {
+    msg: String,
+    arg: Option<String>,
+}
+impl UsageError {
+    fn new<S: Into<String>>(msg: S) -> Self {
+        UsageError { msg: msg.into(), arg: None }
+    }
+    fn at<S: Into<String>>(msg: S, arg: &str) -> Self {
+        UsageError { msg: msg.into(), arg: Some(arg.to_owned()) }
+    }
+}
+
+struct Args {
+    input: PathBuf,
+    opts: CompileOptions,
+    spv_out: PathBuf,
+    spvasm_out: Option<PathBuf>,
+    depfile: Option<PathBuf>,
+    include_graph: Option<PathBuf>,
+    reflect_out: Option<PathBuf>,
+}
+
+/// Macro options translated from the command line, one option per line, and
+/// the arguments they are translated from.
+#[derive(Default)]
+struct MacroOptions {
+    lines: Vec<String>,
+    args: Vec<String>,
+}
+impl MacroOptions {
+    fn push(&mut self, line: String, arg: String) {
+        self.lines.push(line);
+        self.args.push(arg);
+    }
+    /// Parse the options with the grammar of the macros. Errors point at the
+    /// argument the erroneous option is translated from.
+    fn to_compile_options(&self) -> Result<CompileOptions, UsageError> {
+        let src = self.lines.join("\n");
+        let parser = |input: ParseStream| {
+            let mut input = input;
+            let cfg = parse_compile_cfg(&mut input)?;
+            let opts = cfg.to_compile_options()?;
+            Ok((cfg, opts))
+        };
+        let (cfg, opts) = parser.parse_str(&src).map_err(|e| {
+            // Lines are 1-based.
+            let line = e.span().start().line;
+            match line.checked_sub(1).and_then(|i| self.args.get(i)) {
+                Some(arg) => UsageError::at(e.to_string(), arg),
+                None => UsageError::new(e.to_string()),
+            }
+        })?;
+        // Values are checked when they are translated, but make sure no
+        // unsupported option has slipped in anyway.
+        let has_unsupported = cfg.path.is_some() ||
+            !cfg.incl_dir_lists.is_empty() ||
+            !cfg.def_lists.is_empty() ||
+            cfg.dump.is_some() ||
+            cfg.out_version.is_some() ||
+            cfg.depfile.is_some() ||
+            cfg.include_graph.is_some() ||
+            cfg.cache.is_some() ||
+            cfg.resolver.is_some() ||
+            cfg.crate_path.is_some();
+        if has_unsupported {
+            return Err(UsageError::new("option is not supported on the command line"));
+        }
+        Ok(opts)
+    }
+}
+
+/// Check that `value` is a single identifier or literal, so it cannot bring
+/// other options into the translated macro options.
+fn single_token(value: String, arg: &str) -> Result<String, UsageError> {
+    let mut tokens = value.parse::<TokenStream>().into_iter().flatten();
+    match (tokens.next(), tokens.next()) {
+        (Some(TokenTree::Ident(_)), None) | (Some(TokenTree::Literal(_)), None) => Ok(value),
+        _ => Err(UsageError::at("value must be a single identifier or literal", arg)),
+    }
+}
+/// Tokenize the content of a parenthesized list. Delimiters must be balanced
+/// so the list cannot be closed early, and comments are dropped.
+fn token_list(value: String, arg: &str) -> Result<String, UsageError> {
+    value.parse::<TokenStream>()
+        .map(|x| x.to_string())
+        .map_err(|_| UsageError::at("value has unbalanced delimiters", arg))
+}
+
+/// The value of the option `args[*i]`, either after `=` in the argument, or
+/// in the next argument.
+fn take_value(
+    args: &[String],
+    i: &mut usize,
+    inline_value: Option<&str>,
+) -> Result<String, UsageError> {
+    if let Some(value) = inline_value {
+        return Ok(value.to_owned());
+    }
+    let arg = &args[*i];
+    *i += 1;
+    args.get(*i)
+        .cloned()
+        .ok_or_else(|| UsageError::at("option requires a value", arg))
+}
+
+fn parse_args(args: &[String]) -> Result<Option<Args>, UsageError> {
+    let mut input = None;
+    let mut spv_out = None;
+    let mut spvasm_out = None;
+    let mut depfile = None;
+    let mut include_graph = None;
+    let mut reflect_out = None;
+    let mut macro_opts = MacroOptions::default();
+
+    let mut i = 0;
+    while i < args.len() {
+        let start = i;
+        let arg = &args[i];
+        if arg == "-h" || arg == "--help" {
+            return Ok(None);
+        } else if let Some(def) = arg.strip_prefix("-D") {
+            let def = if def.is_empty() { take_value(args, &mut i, None)? } else { def.to_owned() };
+            let line = match def.split_once('=') {
+                Some((k, v)) => format!(", D {} = {:?}", single_token(k.to_owned(), arg)?, v),
+                None => format!(", D {}", single_token(def, arg)?),
+            };
+            macro_opts.push(line, args[start..=i].join(" "));
+        } else if let Some(dir) = arg.strip_prefix("-I") {
+            let dir = if dir.is_empty() { take_value(args, &mut i, None)? } else { dir.to_owned() };
+            macro_opts.push(format!(", I {:?}", dir), args[start..=i].join(" "));
+        } else if arg == "-o" {
+            spv_out = Some(PathBuf::from(take_value(args, &mut i, None)?));
+        } else if let Some(name) = arg.strip_prefix("--") {
+            let (name, inline_value) = match name.split_once('=') {
+                Some((name, value)) => (name, Some(value)),
+                None => (name, None),
+            };
+            let name = name.replace('-', "_");
+            let name = name.as_str();
+            let mut value = || take_value(args, &mut i, inline_value);
+            let line = match name {
+                "spvasm_out" => { spvasm_out = Some(PathBuf::from(value()?)); None },
+                "depfile" => { depfile = Some(PathBuf::from(value()?)); None },
+                "include_graph" => { include_graph = Some(PathBuf::from(value()?)); None },
+                "reflect_out" => { reflect_out = Some(PathBuf::from(value()?)); None },
+                "stage" => {
+                    let stage = single_token(value()?, arg)?;
+                    // Other option keywords like `hlsl` must not pass as a
+                    // stage.
+                    if ShaderStage::from_name(&stage).is_none() {
+                        let arg = args[start..=i].join(" ");
+                        return Err(UsageError::at("unknown shader stage", &arg));
+                    }
+                    Some(format!(", {}", stage))
+                },
+                _ if !OPTION_KEYWORDS.contains(&name) => {
+                    return Err(UsageError::at("unknown option", arg));
+                },
+                _ if UNSUPPORTED_OPTIONS.contains(&name) => {
+                    return Err(UsageError::at("option is not supported on the command line", arg));
+                },
+                _ if STR_OPTIONS.contains(&name) => Some(format!(", {} = {:?}", name, value()?)),
+                _ if RAW_OPTIONS.contains(&name) => {
+                    Some(format!(", {} = {}", name, single_token(value()?, arg)?))
+                },
+                _ if LIST_OPTIONS.contains(&name) => {
+                    Some(format!(", {}({})", name, token_list(value()?, arg)?))
+                },
+                _ if inline_value.is_some() => {
+                    return Err(UsageError::at("option takes no value", arg));
+                },
+                _ => Some(format!(", {}", name)),
+            };
+            if let Some(line) = line {
+                macro_opts.push(line, args[start..=i].join(" "));
+            }
+        } else if arg.starts_with('-') {
+            return Err(UsageError::at("unknown option", arg));
+        } else if input.is_none() {
+            input = Some(PathBuf::from(arg));
+        } else {
+            return Err(UsageError::at("only one input file can be compiled at a time", arg));
+        }
+        i += 1;
+    }
+
+    let input = input.ok_or_else(|| UsageError::new("no input file"))?;
+    let opts = macro_opts.to_compile_options()?;
+    let spv_out = spv_out.unwrap_or_else(|| with_ext(&input, ".spv"));
+    let args = Args { input, opts, spv_out, spvasm_out, depfile, include_graph, reflect_out };
+    Ok(Some(args))
+}
+
+fn with_ext(path: &Path, ext: &str) -> PathBuf {
+    let mut x = OsString::from(path.as_os_str());
+    x.push(ext);
+    PathBuf::from(x)
+}
+
+fn write_file<C: AsRef<[u8]>>(path: &Path, content: C) -> Result<(), String> {
+    if let Some(dir) = path.parent().filter(|x| !x.as_os_str().is_empty()) {
+        std::fs::create_dir_all(dir)
+            .map_err(|e| format!("cannot create directory \"{}\": {}", dir.display(), e))?;
+    }
+    std::fs::write(path, content)
+        .map_err(|e| format!("cannot write to \"{}\": {}", path.display(), e))
+}
+
+#[cfg(feature = "spvasm")]
+fn disassemble(spv: &[u32]) -> Result<String, String> {
+    spirv_compile_core::disassemble_spirv(spv)
+}
+#[cfg(not(feature = "spvasm"))]
+fn disassemble(_: &[u32]) -> Result<String, String> {
+    Err("SPIR-V disassembly requires the `spvasm` feature".to_owned())
+}
+
+fn write_outputs(args: &Args, feedback: &CompilationFeedback) -> Result<(), String> {
+    let bytes = feedback.spv.iter()
+        .flat_map(|x| x.to_le_bytes())
+        .collect::<Vec<u8>>();
+    write_file(&args.spv_out, bytes)?;
+    if let Some(path) = &args.spvasm_out {
+        write_file(path, disassemble(&feedback.spv)?)?;
+    }
+    let graph = DepGraph::from(feedback);
+    if let Some(path) = &args.depfile {
+        write_file(path, graph.to_depfile(&args.spv_out.to_string_lossy()))?;
+    }
+    if let Some(path) = &args.include_graph {
+        write_file(path, graph.to_json())?;
+    }
+    if let Some(path) = &args.reflect_out {
+        write_file(path, reflect::reflect_json(&feedback.spv)?)?;
+    }
+    Ok(())
+}
+
+/// Print a diagnostic with all its lines but the first indented.
+fn print_diag(level: &str, msg: &str) {
+    eprintln!("{}: {}", level, msg.trim_end().replace('\n', "\n  "));
+}
+
+fn main() -> ExitCode {
+    let args = std::env::args().skip(1).collect::<Vec<_>>();
+    let args = match parse_args(&args) {
+        Ok(Some(args)) => args,
+        Ok(None) => {
+            print!("{}", USAGE);
+            return ExitCode::SUCCESS;
+        },
+        Err(e) => {
+            print_diag("error", &e.msg);
+            if let Some(arg) = e.arg {
+                eprintln!("  --> {}", arg);
+            }
+            eprintln!("\nFor more information, try `--help`.");
+            return ExitCode::from(2);
+        },
+    };
+
+    let feedback = Compiler::new()
+        .and_then(|compiler| compiler.compile_file(&args.input, &args.opts));
+    let feedback = match feedback {
+        Ok(x) => x,
+        Err(e) => {
+            print_diag("error", &e);
+            return ExitCode::from(1);
+        },
+    };
+    for warning in feedback.warnings.iter() {
+        print_diag("warning", warning);
+    }
+    if let Err(e) = write_outputs(&args, &feedback) {
+        print_diag("error", &e);
+        return ExitCode::from(1);
+    }
+    ExitCode::SUCCESS
+}
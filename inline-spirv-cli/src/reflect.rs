@@ -0,0 +1,121 @@
+//! SPIR-V reflection for the `--reflect-out` JSON.
+use std::fmt::Write;
+use spirq::prelude::*;
+use spirv_compile_core::ShaderStage;
+
+/// Quote a string as a JSON string.
+pub fn json_str(x: &str) -> String {
+    let mut out = String::with_capacity(x.len() + 2);
+    out.push('"');
+    for c in x.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            c if (c as u32) < 0x20 => { write!(out, "\\u{:04x}", c as u32).unwrap(); },
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+    out
+}
+
+fn name_str(name: &Option<String>) -> String {
+    json_str(name.as_deref().unwrap_or_default())
+}
+
+/// Workgroup size given by the `LocalSize` execution mode of a compute shader.
+fn workgroup_size(entry_point: &EntryPoint) -> Option<[u32; 3]> {
+    let exec_mode = entry_point.exec_modes.iter()
+        .find(|x| x.exec_mode == spirq::spirv::ExecutionMode::LocalSize)?;
+    let mut out = [0; 3];
+    for (x, operand) in out.iter_mut().zip(exec_mode.operands.iter()) {
+        match operand.value {
+            ConstantValue::U32(value) => *x = value,
+            _ => return None,
+        }
+    }
+    Some(out)
+}
+
+/// Describe the entry points and the interface variables of a SPIR-V module
+/// in JSON, as reflected by `spirq`. Variables used by more than one entry
+/// point are listed once.
+pub fn reflect_json(spv: &[u32]) -> Result<String, String> {
+    let entry_points = ReflectConfig::new()
+        .spv(spv)
+        .ref_all_rscs(true)
+        .reflect()
+        .map_err(|e| format!("cannot reflect spirv: {}", e))?;
+
+    let mut out_entry_points = Vec::new();
+    let mut descs = Vec::new();
+    let mut inputs = Vec::new();
+    let mut outputs = Vec::new();
+    let mut push_consts = Vec::new();
+    let mut spec_consts = Vec::new();
+    for entry_point in entry_points.iter() {
+        let stage = ShaderStage::from_execution_model(entry_point.exec_model as u32)
+            .map(|x| json_str(&format!("{:?}", x)))
+            .unwrap_or_else(|| "null".to_owned());
+        let mut out = format!("{{\"name\": {}, \"stage\": {}", json_str(&entry_point.name), stage);
+        if let Some([x, y, z]) = workgroup_size(entry_point) {
+            write!(out, ", \"workgroup_size\": [{}, {}, {}]", x, y, z).unwrap();
+        }
+        out.push('}');
+        out_entry_points.push(out);
+
+        for var in entry_point.vars.iter() {
+            let (items, item) = match var {
+                Variable::Descriptor { name, desc_bind, desc_ty, .. } => {
+                    let item = format!("{{\"name\": {}, \"set\": {}, \"binding\": {}, \"type\": {}}}",
+                        name_str(name), desc_bind.set(), desc_bind.bind(),
+                        json_str(&format!("{:?}", desc_ty)));
+                    (&mut descs, item)
+                },
+                Variable::Input { name, location, .. } => {
+                    let item = format!("{{\"name\": {}, \"location\": {}}}", name_str(name), location.loc());
+                    (&mut inputs, item)
+                },
+                Variable::Output { name, location, .. } => {
+                    let item = format!("{{\"name\": {}, \"location\": {}}}", name_str(name), location.loc());
+                    (&mut outputs, item)
+                },
+                Variable::PushConstant { name, .. } => {
+                    (&mut push_consts, format!("{{\"name\": {}}}", name_str(name)))
+                },
+                Variable::SpecConstant { name, spec_id, .. } => {
+                    let item = format!("{{\"name\": {}, \"id\": {}}}", name_str(name), spec_id);
+                    (&mut spec_consts, item)
+                },
+            };
+            if !items.contains(&item) {
+                items.push(item);
+            }
+        }
+    }
+
+    let mut out = "{".to_owned();
+    let sections = [
+        ("entry_points", out_entry_points),
+        ("descriptors", descs),
+        ("inputs", inputs),
+        ("outputs", outputs),
+        ("push_constants", push_consts),
+        ("spec_constants", spec_consts),
+    ];
+    for (i, (key, items)) in sections.iter().enumerate() {
+        if i > 0 {
+            out.push(',');
+        }
+        write!(out, "\n  {}: [", json_str(key)).unwrap();
+        if !items.is_empty() {
+            write!(out, "\n    {}\n  ", items.join(",\n    ")).unwrap();
+        }
+        out.push(']');
+    }
+    out.push_str("\n}\n");
+    Ok(out)
+}
@@ -0,0 +1,99 @@
+use std::process::{Command, Output};
+
+fn run(args: &[&str]) -> Output {
+    Command::new(env!("CARGO_BIN_EXE_inline-spirv"))
+        .args(args)
+        .output()
+        .unwrap()
+}
+fn stderr(out: &Output) -> String {
+    String::from_utf8_lossy(&out.stderr).into_owned()
+}
+
+#[test]
+fn usage_errors_point_at_the_argument() {
+    let cases: &[(&[&str], &str)] = &[
+        (&["a.frag", "--bogus"], "--> --bogus"),
+        (&["a.frag", "--glsl_version", "abc"], "--> --glsl_version abc"),
+        (&["a.frag", "--stage", "nope"], "--> --stage nope"),
+        (&["a.frag", "--stage", "hlsl"], "--> --stage hlsl"),
+        (&["a.frag", "--max-perf=1"], "--> --max-perf=1"),
+        (&["a.frag", "--entry"], "--> --entry"),
+        (&["a.frag", "b.frag"], "--> b.frag"),
+    ];
+    for (args, expected) in cases {
+        let out = run(args);
+        assert_eq!(out.status.code(), Some(2), "{:?}", args);
+        assert!(stderr(&out).contains(expected), "{:?}: {}", args, stderr(&out));
+    }
+}
+
+#[test]
+fn macro_only_options_are_rejected() {
+    for opt in ["--dump=x", "--out_version=x", "--cache=x", "--resolver=x", "--crate=x"] {
+        let out = run(&["a.frag", opt]);
+        assert_eq!(out.status.code(), Some(2), "{}", opt);
+        assert!(stderr(&out).contains("not supported on the command line"), "{}", opt);
+    }
+}
+
+#[test]
+fn values_cannot_smuggle_options() {
+    let cases: &[&[&str]] = &[
+        &["a.frag", "--glsl_version", "450, dump = \"x\""],
+        &["a.frag", "--profile=core, crate = x"],
+        &["a.frag", "--stage", "frag, cache = &*CACHE"],
+        &["a.frag", "--limit", "MaxLights=8), dump = (\"x\""],
+        &["a.frag", "-D", "X, dump = \"x\""],
+        &["a.frag", "-DX, resolver = r=1"],
+    ];
+    for args in cases {
+        let out = run(args);
+        assert_eq!(out.status.code(), Some(2), "{:?}", args);
+    }
+}
+
+#[test]
+fn missing_inputs_fail_to_compile() {
+    let out = run(&["missing.frag"]);
+    assert_eq!(out.status.code(), Some(1));
+    assert!(stderr(&out).starts_with("error: cannot read from"), "{}", stderr(&out));
+}
+
+#[cfg(feature = "wgsl")]
+#[test]
+fn wgsl_outputs_are_written() {
+    use std::path::PathBuf;
+    let dir = PathBuf::from(env!("CARGO_TARGET_TMPDIR")).join("wgsl_outputs_are_written");
+    std::fs::create_dir_all(&dir).unwrap();
+    let src_path = dir.join("shader.wgsl");
+    std::fs::write(&src_path, r#"
+        @group(1) @binding(2) var<uniform> color: vec4<f32>;
+        @fragment
+        fn fs_main() -> @location(0) vec4<f32> { return color; }
+    "#).unwrap();
+    let spv_path = dir.join("out/shader.spv");
+    let depfile_path = dir.join("out/shader.d");
+    let reflect_path = dir.join("out/shader.json");
+    let graph_path = dir.join("out/shader.deps.json");
+    let out = run(&[
+        src_path.to_str().unwrap(),
+        "--frag", "--entry", "fs_main",
+        "-o", spv_path.to_str().unwrap(),
+        "--depfile", depfile_path.to_str().unwrap(),
+        "--include-graph", graph_path.to_str().unwrap(),
+        "--reflect-out", reflect_path.to_str().unwrap(),
+    ]);
+    assert!(out.status.success(), "{}", stderr(&out));
+
+    let spv = std::fs::read(&spv_path).unwrap();
+    assert_eq!(spv[..4], 0x07230203u32.to_le_bytes());
+    let depfile = std::fs::read_to_string(&depfile_path).unwrap();
+    assert!(depfile.starts_with(&format!("{}:", spv_path.display())), "{}", depfile);
+    assert!(depfile.contains("shader.wgsl"), "{}", depfile);
+    let graph = std::fs::read_to_string(&graph_path).unwrap();
+    assert!(graph.contains("shader.wgsl\"],\n  \"includes\": []"), "{}", graph);
+    let reflect = std::fs::read_to_string(&reflect_path).unwrap();
+    assert!(reflect.contains(r#"{"name": "fs_main", "stage": "Fragment"}"#), "{}", reflect);
+    assert!(reflect.contains(r#""set": 1, "binding": 2"#), "{}", reflect);
+}
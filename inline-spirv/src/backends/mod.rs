@@ -1,3 +1 @@
-pub mod shaderc;
-pub mod naga;
-pub mod spirq_spvasm;
+pub mod naga_cross;
@@ -0,0 +1,171 @@
+#[cfg(feature = "cross")]
+use spirv_compile_core::{CompilationFeedback, CompileOptions, ShaderStage, SourceLanguage};
+#[cfg(feature = "cross")]
+use spirv_compile_core::syntax::ShaderCompilationConfig;
+
+/// Shading language to translate into.
+#[cfg(feature = "cross")]
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub(crate) enum CrossTarget {
+    Msl,
+    Hlsl,
+    Glsl,
+    GlslEs,
+    Wgsl,
+}
+/// Shader to be translated, either in source text or precompiled SPIR-V.
+#[cfg(feature = "cross")]
+pub(crate) enum CrossSource<'a> {
+    Text(&'a str),
+    Spirv(&'a [u32]),
+}
+
+#[cfg(feature = "cross")]
+fn parse_version(ver: &str) -> Option<(u8, u8)> {
+    let (major, minor) = ver.split_once('.')?;
+    Some((major.parse().ok()?, minor.parse().ok()?))
+}
+
+#[cfg(feature = "cross")]
+fn parse_spirv(
+    spv: &[u32],
+    cfg: &ShaderCompilationConfig,
+) -> Result<naga::Module, String> {
+    let opts = naga::front::spv::Options {
+        adjust_coordinate_space: cfg.y_flip,
+        strict_capabilities: false,
+        block_ctx_dump_prefix: None,
+    };
+    let bytes = spv.iter()
+        .flat_map(|x| x.to_le_bytes())
+        .collect::<Vec<u8>>();
+    naga::front::spv::parse_u8_slice(&bytes, &opts)
+        .map_err(|e| format!("{:?}", e))
+}
+
+/// Find the entry point named by `entry`, or the first entry point of the
+/// requested stage if there is no such one.
+#[cfg(feature = "cross")]
+fn find_entry_point<'a>(
+    module: &'a naga::Module,
+    cfg: &ShaderCompilationConfig,
+) -> Result<&'a naga::EntryPoint, String> {
+    let stage = match cfg.stage {
+        Some(ShaderStage::Vertex) => Some(naga::ShaderStage::Vertex),
+        Some(ShaderStage::Fragment) => Some(naga::ShaderStage::Fragment),
+        Some(ShaderStage::Compute) => Some(naga::ShaderStage::Compute),
+        None => None,
+        _ => return Err("unsupported shader kind".to_owned()),
+    };
+    let is_stage_matched = |ep: &naga::EntryPoint| stage.is_none() || stage == Some(ep.stage);
+    module.entry_points.iter()
+        .find(|ep| ep.name == cfg.entry && is_stage_matched(ep))
+        .or_else(|| module.entry_points.iter().find(|ep| is_stage_matched(ep)))
+        .ok_or(format!("cannot find entry point \"{}\"", cfg.entry))
+}
+
+/// Translate the shader into another shading language with `naga`. Sources in
+/// any other languages than WGSL are compiled to SPIR-V with the other backends
+/// first. Returns the translated source and the feedback of the compilation,
+/// which has no SPIR-V if the source is WGSL.
+#[cfg(feature = "cross")]
+pub(crate) fn translate(
+    src: CrossSource,
+    opts: &CompileOptions,
+    cfg: &ShaderCompilationConfig,
+    target: CrossTarget,
+) -> Result<(String, CompilationFeedback), String> {
+    use naga::valid::{Capabilities, ValidationFlags, Validator};
+    let path = opts.source_path();
+
+    let dep_paths = path.into_iter().map(|x| x.to_owned()).collect();
+    let (module, feedback) = match src {
+        CrossSource::Text(src) if cfg.lang == Some(SourceLanguage::Wgsl) => {
+            let module = naga::front::wgsl::parse_str(src)
+                .map_err(|e| e.emit_to_string(src))?;
+            (module, CompilationFeedback { dep_paths, ..Default::default() })
+        },
+        CrossSource::Text(src) => {
+            let feedback = crate::compile(src, opts)?;
+            (parse_spirv(&feedback.spv, cfg)?, feedback)
+        },
+        CrossSource::Spirv(spv) => {
+            let feedback = CompilationFeedback {
+                spv: spv.to_vec(),
+                dep_paths,
+                ..Default::default()
+            };
+            (parse_spirv(spv, cfg)?, feedback)
+        },
+    };
+    let info = Validator::new(ValidationFlags::all(), Capabilities::all())
+        .validate(&module)
+        .map_err(|e| format!("{:?}", e))?;
+
+    let out_version = cfg.out_version.as_deref();
+    let invalid_version = || format!("invalid output version \"{}\"",
+        out_version.unwrap_or_default());
+    let out = match target {
+        CrossTarget::Msl => {
+            let mut opts = naga::back::msl::Options::default();
+            if let Some(ver) = out_version {
+                opts.lang_version = parse_version(ver).ok_or_else(invalid_version)?;
+            }
+            let pipe_opts = naga::back::msl::PipelineOptions::default();
+            naga::back::msl::write_string(&module, &info, &opts, &pipe_opts)
+                .map_err(|e| format!("{:?}", e))?
+                .0
+        },
+        CrossTarget::Hlsl => {
+            use naga::back::hlsl::ShaderModel;
+            let mut opts = naga::back::hlsl::Options::default();
+            opts.shader_model = match out_version {
+                None => opts.shader_model,
+                Some("5.0") => ShaderModel::V5_0,
+                Some("5.1") => ShaderModel::V5_1,
+                Some("6.0") => ShaderModel::V6_0,
+                Some(_) => return Err(invalid_version()),
+            };
+            let mut out = String::new();
+            naga::back::hlsl::Writer::new(&mut out, &opts)
+                .write(&module, &info)
+                .map_err(|e| format!("{:?}", e))?;
+            out
+        },
+        CrossTarget::Glsl | CrossTarget::GlslEs => {
+            use naga::back::glsl::Version;
+            let ver = out_version
+                .map(|x| x.parse::<u16>().map_err(|_| invalid_version()))
+                .transpose()?;
+            let version = match target {
+                CrossTarget::GlslEs => Version::new_gles(ver.unwrap_or(310)),
+                _ => Version::Desktop(ver.unwrap_or(450)),
+            };
+            let opts = naga::back::glsl::Options {
+                version,
+                ..Default::default()
+            };
+            let entry_point = find_entry_point(&module, cfg)?;
+            let pipe_opts = naga::back::glsl::PipelineOptions {
+                shader_stage: entry_point.stage,
+                entry_point: entry_point.name.clone(),
+                multiview: None,
+            };
+            let mut out = String::new();
+            naga::back::glsl::Writer::new(&mut out, &module, &info, &opts,
+                &pipe_opts, naga::proc::BoundsCheckPolicies::default())
+                .and_then(|mut writer| writer.write())
+                .map_err(|e| format!("{:?}", e))?;
+            out
+        },
+        CrossTarget::Wgsl => {
+            if out_version.is_some() {
+                return Err("wgsl output is not versioned".to_owned());
+            }
+            naga::back::wgsl::write_string(&module, &info,
+                naga::back::wgsl::WriterFlags::empty())
+                .map_err(|e| format!("{:?}", e))?
+        },
+    };
+    Ok((out, feedback))
+}
@@ -53,7 +53,7 @@
 //! - `min_size`: Optimize for the minimal output size;
 //! - `max_perf`: Optimize for the best performance;
 //! - `no_debug`: Strip off all the debug information (don't do this if you want
-//! to reflect the SPIR-V and get variable names).
+//!   to reflect the SPIR-V and get variable names).
 //!
 //! ## Include External Source
 //!
@@ -80,6 +80,57 @@
 //! Note that all compile arguments are ignored in this case, since there is no
 //! compilation.
 //!
+//! ## Link Multiple Modules
+//!
+//! If you keep shared utility functions in separate modules with export
+//! linkage, you can compile and link them into a single module with
+//! `link_spirv!`. Paths ending with `.spv` are taken as precompiled binaries,
+//! `.spvasm` as SPIR-V assembly, and all the others are compiled with the
+//! following compile arguments:
+//!
+//! ```ignore
+//! link_spirv!("path/to/shader.hlsl", "path/to/utils.spv", hlsl, frag,
+//!     entry="very_main");
+//! ```
+//!
+//! The modules are linked by `spirv-link` from
+//! [SPIRV-Tools](https://github.com/KhronosGroup/SPIRV-Tools), which is looked
+//! up in `PATH` unless you specify its location in the `SPIRV_LINK` environment
+//! variable. Only the entry point named by `entry` is kept in the linked module.
+//!
+//! ## Inspect Compiled SPIR-V
+//!
+//! To see what your shader is compiled to, `inline_spirv_disasm!` and
+//! `include_spirv_disasm!` take the same arguments as their counterparts but
+//! give you the SPIR-V assembly of the compiled shader in `&'static str`:
+//!
+//! ```ignore
+//! let text: &'static str = include_spirv_disasm!("path/to/shader.hlsl", hlsl, vert);
+//! ```
+//!
+//! You can also dump the SPIR-V binary and its assembly to files with
+//! `dump="path/to/stem"`, which writes `path/to/stem.spv` and
+//! `path/to/stem.spvasm` relative to the crate root, so you can inspect them
+//! with RenderDoc or `spirv-cross`:
+//!
+//! ```ignore
+//! include_spirv!("path/to/shader.hlsl", hlsl, vert, dump="target/shaders/shader");
+//! ```
+//!
+//! ## Dependency Files
+//!
+//! For asset pipelines and build systems like Ninja, `depfile="path/to/file.d"`
+//! writes a Makefile-style depfile listing the source file and all the files
+//! it includes, and `include_graph="path/to/file.json"` writes the include
+//! graph in JSON, both relative to the crate root. The target of the depfile
+//! rule is the dumped binary if `dump` is given, or the depfile itself
+//! otherwise:
+//!
+//! ```ignore
+//! include_spirv!("path/to/shader.glsl", frag, dump="target/shaders/shader",
+//!     depfile="target/shaders/shader.d", include_graph="target/shaders/shader.json");
+//! ```
+//!
 //! ## Compiler Definition
 //!
 //! You can also define macro substitutions:
@@ -90,6 +141,11 @@
 //!     D LIGHTMAP_COUNT="2");
 //! ```
 //!
+//! SPIR-V assembly sources go through a C-style preprocessor too, so
+//! `#include`, `#define`, `#ifdef`, `#ifndef`, `#else` and `#endif` work as you
+//! expect and the definitions are substituted into the assembly. IDs like
+//! `%NAME` and string literals are never substituted.
+//!
 //! You can request a specific version of target environment:
 //! - `vulkan1_0` for Vulkan 1.0 (default, supports SPIR-V 1.0);
 //! - `vulkan1_1` for Vulkan 1.1 (supports SPIR-V 1.3);
@@ -97,10 +153,34 @@
 //! - `opengl4_5` for OpenGL 4.5 core profile.
 //! - `webgpu` for WebGPU.
 //!
+//! The SPIR-V version can also be forced with `spirq1_0` to `spirq1_6` after
+//! the target environment. GLSL, HLSL and WGSL sources are then compiled for
+//! the Vulkan version supporting it, e.g., `spirq1_3` for Vulkan 1.1.
+//!
 //! Of course once you started to use macro is basically means that you are
 //! getting so dynamic that this little crate might not be enough. Then it might
 //! be a good time to build your own shader compilation pipeline!
 //!
+//! ## GLSL Version and Resource Limits
+//!
+//! For GLSL sources without a `#version` directive, or to override it, you can
+//! force the GLSL version and profile with `glsl_version=450` and
+//! `profile=core`, `profile=compatibility` or `profile=es`. If the profile is
+//! omitted, `es` is assumed for ES versions (100, 300, 310 and 320) and `core`
+//! for versions since 150.
+//!
+//! Shaders can be checked against the resource limits of your target devices
+//! with `limit(...)`, and/or by loading a
+//! [glslang resource limit file](https://github.com/KhronosGroup/glslang/blob/main/glslang/ResourceLimits/ResourceLimits.cpp)
+//! (relative to the crate root) with `limits_file`. Limits in `limit(...)` take precedence:
+//!
+//! ```ignore
+//! include_spirv!("path/to/shader.glsl", comp,
+//!     glsl_version=450, profile=core,
+//!     limits_file="limits/mobile.conf",
+//!     limit(MaxComputeWorkGroupSizeX=1024, MaxClipDistances=8));
+//! ```
+//!
 //! ## Descriptor Auto-binding
 //!
 //! If you are just off your work being tooooo tired to specify the descriptor
@@ -118,6 +198,51 @@
 //! However, if you don't have any automated reflection tool to get the actual
 //! binding points, it's not recommended to use this.
 //!
+//! ## HLSL Options
+//!
+//! HLSL sources can be compiled with some extra options:
+//!
+//! - `shader_model="6.2"`: Define the `__SHADER_TARGET_MAJOR` and
+//!   `__SHADER_TARGET_MINOR` macros of the given shader model, as DXC does. The
+//!   HLSL frontend of shaderc has no notion of shader models, so the code
+//!   generation is not affected. Only shader models 4.0, 4.1, 5.0, 5.1 and 6.0
+//!   to 6.8 are accepted;
+//! - `hlsl_16bit_types`: Enable 16-bit types like `half` and `min16float`
+//!   (requires shader model 6.2 or above if one is specified);
+//! - `hlsl_offsets`: Use HLSL packing rules instead of GLSL rules for buffer
+//!   member offsets;
+//! - `hlsl_io_mapping`: Use HLSL IO mappings for bindings;
+//! - `hlsl_functionality1`: Emit the `SPV_GOOGLE_hlsl_functionality1`
+//!   extension, which keeps semantic strings for reflection;
+//! - `hlsl_explicit_binding("t0", 0, 1)`: Assign the descriptor set and binding
+//!   of a register explicitly. This can be specified multiple times;
+//! - `hlsl_explicit_binding_for_stage(frag, "t0", 0, 1)`: Same as above but
+//!   only effective for the given stage.
+//!
+//! ## Descriptor Binding Base
+//!
+//! Bindings of each kind of resource can be shifted by a base binding number,
+//! which applies to automatically assigned bindings and HLSL registers alike.
+//! This is useful to compile HLSL code written for D3D register conventions to
+//! deterministic Vulkan bindings where `t#`, `s#`, `b#` and `u#` registers
+//! don't collide:
+//!
+//! ```ignore
+//! include_spirv!("path/to/shader.hlsl", hlsl, frag,
+//!     binding_base(texture=0, sampler=16, buffer=32, uav=48),
+//!     binding_base_for_stage(vert, buffer=64));
+//! ```
+//!
+//! The resource kinds are:
+//!
+//! - `image`: Images and image buffers;
+//! - `sampler`: Pure samplers (`s#` registers in HLSL);
+//! - `texture`: Sampled textures in GLSL and shader resource views in HLSL
+//!   (`t#` registers);
+//! - `buffer`: Uniform buffers in GLSL and cbuffers in HLSL (`b#` registers);
+//! - `storage_buffer`: Shader storage buffers in GLSL;
+//! - `uav`: Unordered access views in HLSL (`u#` registers).
+//!
 //! ## Flip-Y for WebGPU
 //!
 //! If you intend to compile WGSL for a WebGPU backend, `naga` by default
@@ -125,6 +250,100 @@
 //! Coordinates) between WebGPU and Vulkan. If such correction is undesired, you
 //! can opt out with `no_y_flip`.
 //!
+//! ## Cross Compilation
+//!
+//! When the `cross` feature is enabled, shaders can also be translated into
+//! other shading languages by `naga` at build time for renderers on platforms
+//! without Vulkan. The following macros take the same arguments as
+//! `inline_spirv!` and `include_spirv!` respectively but return the translated
+//! source in `&'static str`:
+//!
+//! - `inline_msl!`/`include_msl!`: Metal Shading Language;
+//! - `inline_hlsl!`/`include_hlsl!`: HLSL;
+//! - `inline_glsl!`/`include_glsl!`: Desktop GLSL;
+//! - `inline_glsl_es!`/`include_glsl_es!`: GLSL ES;
+//! - `inline_wgsl!`/`include_wgsl!`: WGSL.
+//!
+//! WGSL sources are parsed by `naga` directly; sources in other languages are
+//! compiled to SPIR-V first and then translated. `include_*!` also accepts
+//! precompiled SPIR-V binaries. The output language version can be specified
+//! with `out_version`, e.g., `out_version="2.1"` for MSL 2.1,
+//! `out_version="6.0"` for HLSL shader model 6.0, and `out_version="300"` for
+//! GLSL ES 3.00:
+//!
+//! ```ignore
+//! let msl: &'static str = include_msl!("path/to/shader.hlsl", hlsl, frag,
+//!     out_version="2.1");
+//! ```
+//!
+//! GLSL outputs contain only one entry point, which is the one named by `entry`
+//! or otherwise the first entry point of the specified stage.
+//!
+//! `dump`, `depfile` and `include_graph` work as in `include_spirv!`. `dump`
+//! writes the SPIR-V the shader is translated from, so it's not supported for
+//! WGSL sources, which are translated directly.
+//!
+//! ## SPIR-V Assembly Header
+//!
+//! The SPIR-V module header of assembled `spvasm` sources can be taken from the
+//! header comments at the beginning of the source, just as `spirv-dis` prints
+//! them, so disassembled binaries are assembled back bit-exactly:
+//!
+//! ```text
+//! ; SPIR-V
+//! ; Version: 1.3
+//! ; Generator: Khronos Glslang Reference Front End; 10
+//! ; Bound: 32
+//! ; Schema: 0
+//! ```
+//!
+//! The version in the header comments overrides the target SPIR-V version,
+//! unless the version is forced with `spirq1_0` to `spirq1_6`. You can also
+//! specify the generator magic number with `generator=<u32>`, and an ID bound
+//! with `bound=<u32>`, which override the header comments. The bound must not
+//! be smaller than what the assembly actually uses.
+//!
+//! ## Options Shared with `jit-spirv`
+//!
+//! `inline-spirv` and `jit-spirv` parse the options with the same grammar from
+//! `spirv-compile-core` and compile with the same backends, so a shader can be
+//! moved between build time and runtime without changing its options. Inline
+//! sources can be given a path with `path, "path/to/shader.glsl"` to resolve
+//! relative `#include`s. Options evaluated at runtime, i.e., `include_dirs`,
+//! `defines`, `cache`, `resolver` and definitions or include directories that
+//! are not string literals, are rejected by `inline-spirv`; while `dump`,
+//! `out_version`, `depfile` and `include_graph` are rejected by `jit-spirv`.
+//!
+//! ## Shader Manifest
+//!
+//! With the `manifest` feature, all the shaders of a crate can be declared
+//! once in a manifest file, and compiled to a module of `pub static` shader
+//! constants with `shader_manifest!`:
+//!
+//! ```ignore
+//! mod shaders {
+//!     inline_spirv::shader_manifest!("assets/shaders.toml");
+//! }
+//! let spv: &'static [u32] = shaders::WATER_QUALITY_HIGH;
+//! ```
+//!
+//! ```toml
+//! [defaults]
+//! target = "vulkan1_2"
+//! include_dirs = ["include"]
+//!
+//! [shaders.water]
+//! path = "water.hlsl"
+//! stage = "frag"
+//! defines = { USE_COLOR = true }
+//! permutations = { QUALITY = ["LOW", "HIGH"] }
+//! ```
+//!
+//! Paths in the manifest are relative to the manifest. Please refer to
+//! `spirv_compile_core::manifest` for the full format. Invalid manifests are
+//! reported with the line and the column of the invalid value. The same
+//! manifest can also be compiled in a build script with `inline-spirv-build`.
+//!
 //! ## Tips
 //!
 //! The macro can be verbose especially you have a bunch of `#include`s, so
@@ -159,101 +378,35 @@ mod backends;
 use proc_macro::TokenStream;
 use quote::quote;
 use syn::parse::{Parse, ParseStream, Result as ParseResult, Error as ParseError};
-use syn::{parse_macro_input, Ident, LitStr, Token};
+use syn::{parse_macro_input, LitStr, Token};
+use spirv_compile_core::syntax::{parse_compile_cfg, ShaderCompilationConfig};
+use spirv_compile_core::{CompilationFeedback, CompileOptions, Compiler, DepGraph,
+    SourceLanguage};
 
-#[derive(Clone, Copy, PartialEq, Eq)]
-enum InputSourceLanguage {
-    Unknown,
-    Glsl,
-    Hlsl,
-    Wgsl,
-    Spvasm,
-}
-#[derive(Clone, Copy, PartialEq, Eq)]
-enum TargetSpirvVersion {
-    Spirv1_0,
-    Spirv1_1,
-    Spirv1_2,
-    Spirv1_3,
-    Spirv1_4,
-    Spirv1_5,
-    Spirv1_6,
-}
-#[derive(Clone, Copy, PartialEq, Eq)]
-enum TargetEnvironmentType {
-    Vulkan,
-    OpenGL,
-    WebGpu,
-}
-#[derive(Clone, Copy, PartialEq, Eq)]
-enum OptimizationLevel {
-    MinSize,
-    MaxPerformance,
-    None,
-}
-#[derive(Clone, Copy, PartialEq, Eq)]
-enum ShaderKind {
-    Unknown,
+struct InlineShaderSource(CompilationFeedback);
+struct IncludedShaderSource(CompilationFeedback);
+struct LinkedShaderSource(CompilationFeedback);
 
-    Vertex,
-    TesselationControl,
-    TesselationEvaluation,
-    Geometry,
-    Fragment,
-    Compute,
-    // Mesh Pipeline
-    Mesh,
-    Task,
-    // Ray-tracing Pipeline
-    RayGeneration,
-    Intersection,
-    AnyHit,
-    ClosestHit,
-    Miss,
-    Callable,
+#[cfg(feature = "cross")]
+enum CrossInput {
+    Text(String),
+    Spirv(Vec<u32>),
 }
-
-struct ShaderCompilationConfig {
-    lang: InputSourceLanguage,
-    incl_dirs: Vec<PathBuf>,
-    defs: Vec<(String, Option<String>)>,
-    spv_ver: TargetSpirvVersion,
-    env_ty: TargetEnvironmentType,
-    entry: String,
-    optim_lv: OptimizationLevel,
-    debug: bool,
-    kind: ShaderKind,
-    auto_bind: bool,
-    // Backend specific.
-    #[cfg(feature = "naga")]
-    y_flip: bool,
-}
-impl Default for ShaderCompilationConfig {
-    fn default() -> Self {
-        ShaderCompilationConfig {
-            lang: InputSourceLanguage::Unknown,
-            incl_dirs: Vec::new(),
-            defs: Vec::new(),
-            spv_ver: TargetSpirvVersion::Spirv1_0,
-            env_ty: TargetEnvironmentType::Vulkan,
-            entry: "main".to_owned(),
-            optim_lv: OptimizationLevel::None,
-            debug: true,
-            kind: ShaderKind::Unknown,
-            auto_bind: false,
-
-            #[cfg(feature = "naga")]
-            y_flip: true,
-        }
-    }
+#[cfg(feature = "cross")]
+struct CrossShaderSource {
+    input: CrossInput,
+    opts: CompileOptions,
+    cfg: ShaderCompilationConfig,
 }
-
-struct CompilationFeedback {
-    spv: Vec<u32>,
-    dep_paths: Vec<String>,
+#[cfg(feature = "cross")]
+struct InlineCrossSource(CrossShaderSource);
+#[cfg(feature = "cross")]
+struct IncludedCrossSource(CrossShaderSource);
+#[cfg(feature = "manifest")]
+struct ManifestShaders {
+    manifest_path: String,
+    shaders: Vec<(spirv_compile_core::manifest::ManifestShader, CompilationFeedback)>,
 }
-struct InlineShaderSource(CompilationFeedback);
-struct IncludedShaderSource(CompilationFeedback);
 
 #[inline]
 fn get_base_dir() -> PathBuf {
@@ -266,139 +419,104 @@ fn parse_str(input: &mut ParseStream) -> ParseResult<String> {
     input.parse::<LitStr>()
         .map(|x| x.value())
 }
-#[inline]
-fn parse_ident(input: &mut ParseStream) -> ParseResult<String> {
-    input.parse::<Ident>()
-        .map(|x| x.to_string())
-}
-
-fn parse_compile_cfg(
-    input: &mut ParseStream
-) -> ParseResult<ShaderCompilationConfig> {
-    let mut cfg = ShaderCompilationConfig::default();
-    while !input.is_empty() {
-        use syn::Error;
-        // Capture comma and collon; they are for readability.
-        input.parse::<Token![,]>()?;
-        let k = if let Ok(k) = input.parse::<Ident>() { k } else { break };
-        match &k.to_string() as &str {
-            "glsl" => cfg.lang = InputSourceLanguage::Glsl,
-            "hlsl" => {
-                cfg.lang = InputSourceLanguage::Hlsl;
-                // HLSL might be illegal if optimization is disabled. Not sure,
-                // `glslangValidator` said this.
-                cfg.optim_lv = OptimizationLevel::MaxPerformance;
-            },
-            "wgsl" => cfg.lang = InputSourceLanguage::Wgsl,
-            "spvasm" => cfg.lang = InputSourceLanguage::Spvasm,
-
-            "vert" => cfg.kind = ShaderKind::Vertex,
-            "tesc" => cfg.kind = ShaderKind::TesselationControl,
-            "tese" => cfg.kind = ShaderKind::TesselationEvaluation,
-            "geom" => cfg.kind = ShaderKind::Geometry,
-            "frag" => cfg.kind = ShaderKind::Fragment,
-            "comp" => cfg.kind = ShaderKind::Compute,
-            "mesh" => cfg.kind = ShaderKind::Mesh,
-            "task" => cfg.kind = ShaderKind::Task,
-            "rgen" => cfg.kind = ShaderKind::RayGeneration,
-            "rint" => cfg.kind = ShaderKind::Intersection,
-            "rahit" => cfg.kind = ShaderKind::AnyHit,
-            "rchit" => cfg.kind = ShaderKind::ClosestHit,
-            "rmiss" => cfg.kind = ShaderKind::Miss,
-            "rcall" => cfg.kind = ShaderKind::Callable,
-
-            "I" => {
-                cfg.incl_dirs.push(PathBuf::from(parse_str(input)?))
-            },
-            "D" => {
-                let k = parse_ident(input)?;
-                let v = if input.parse::<Token![=]>().is_ok() {
-                    Some(parse_str(input)?)
-                } else { None };
-                cfg.defs.push((k, v));
-            },
-
-            "entry" => {
-                if input.parse::<Token![=]>().is_ok() {
-                    cfg.entry = parse_str(input)?.to_owned();
-                }
-            }
-
-            "min_size" => cfg.optim_lv = OptimizationLevel::MinSize,
-            "max_perf" => cfg.optim_lv = OptimizationLevel::MaxPerformance,
-
-            "no_debug" => cfg.debug = false,
 
-            "vulkan" | "vulkan1_0" => {
-                cfg.env_ty = TargetEnvironmentType::Vulkan;
-                cfg.spv_ver = TargetSpirvVersion::Spirv1_0;
-            },
-            "vulkan1_1" => {
-                cfg.env_ty = TargetEnvironmentType::Vulkan;
-                cfg.spv_ver = TargetSpirvVersion::Spirv1_3;
-            },
-            "vulkan1_2" => {
-                cfg.env_ty = TargetEnvironmentType::Vulkan;
-                cfg.spv_ver = TargetSpirvVersion::Spirv1_5;
-            },
-            "opengl" | "opengl4_5" => {
-                cfg.env_ty = TargetEnvironmentType::OpenGL;
-                cfg.spv_ver = TargetSpirvVersion::Spirv1_0;
-            },
-            "webgpu" => {
-                cfg.env_ty = TargetEnvironmentType::WebGpu;
-                cfg.spv_ver = TargetSpirvVersion::Spirv1_0;
-            }
-
-            "spirq1_0" => cfg.spv_ver = TargetSpirvVersion::Spirv1_0,
-            "spirq1_1" => cfg.spv_ver = TargetSpirvVersion::Spirv1_1,
-            "spirq1_2" => cfg.spv_ver = TargetSpirvVersion::Spirv1_2,
-            "spirq1_3" => cfg.spv_ver = TargetSpirvVersion::Spirv1_3,
-            "spirq1_4" => cfg.spv_ver = TargetSpirvVersion::Spirv1_4,
-            "spirq1_5" => cfg.spv_ver = TargetSpirvVersion::Spirv1_5,
-            "spirq1_6" => cfg.spv_ver = TargetSpirvVersion::Spirv1_6,
-
-            "auto_bind" => cfg.auto_bind = true,
-
-            #[cfg(feature = "naga")]
-            "no_y_flip" => cfg.y_flip = false,
-
-            _ => return Err(Error::new(k.span(), "unsupported compilation parameter")),
-        }
+/// Lower the macro options for compilation at build time. Paths in the
+/// options are relative to the crate root.
+fn compile_options(cfg: &ShaderCompilationConfig) -> ParseResult<CompileOptions> {
+    let mut opts = cfg.to_compile_options()?;
+    if let Some(path) = &cfg.path {
+        opts = opts.path(get_base_dir().join(path).to_string_lossy());
+    }
+    if let Some(limits_file) = &cfg.limits_file {
+        opts = opts.limits_file(get_base_dir().join(limits_file));
     }
-    Ok(cfg)
+    Ok(opts)
 }
 
 fn compile(
     src: &str,
-    path: Option<&str>,
-    cfg: &ShaderCompilationConfig,
+    opts: &CompileOptions,
 ) -> Result<CompilationFeedback, String> {
-    match backends::spirq_spvasm::compile(src, path, cfg) {
-        Ok(x) => return Ok(x),
-        Err(e) if e != "unsupported source language" => return Err(e),
-        _ => {}
+    reject_warnings(Compiler::new()?.compile(src, opts)?)
+}
+fn reject_warnings(feedback: CompilationFeedback) -> Result<CompilationFeedback, String> {
+    // There is no way to report warnings from a proc macro, so don't let them
+    // pass silently.
+    if !feedback.warnings.is_empty() {
+        return Err(feedback.warnings.join("\n"));
     }
-    #[cfg(feature = "shaderc")]
-    match backends::shaderc::compile(src, path, cfg) {
-        Ok(x) => return Ok(x),
-        Err(e) if e != "unsupported source language" => return Err(e),
-        _ => {}
+    Ok(feedback)
+}
+
+/// Write the SPIR-V binary and its disassembly to `{path}.spv` and
+/// `{path}.spvasm` for inspection in external tools.
+fn dump_spirv(path: &Path, spv: &[u32]) -> Result<(), String> {
+    use std::ffi::OsString;
+    let path = get_base_dir().join(path);
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir)
+            .map_err(|e| format!("cannot create dump directory: {}", e))?;
     }
-    #[cfg(feature = "naga")]
-    match backends::naga::compile(src, path, cfg) {
-        Ok(x) => return Ok(x),
-        Err(e) if e != "unsupported source language" => return Err(e),
-        _ => {}
+    let with_ext = |ext: &str| {
+        let mut x = OsString::from(path.as_os_str());
+        x.push(ext);
+        PathBuf::from(x)
+    };
+
+    let bytes = spv.iter()
+        .flat_map(|x| x.to_le_bytes())
+        .collect::<Vec<u8>>();
+    std::fs::write(with_ext(".spv"), bytes)
+        .map_err(|e| format!("cannot dump spirv: {}", e))?;
+    let text = spirv_compile_core::disassemble_spirv(spv)?;
+    std::fs::write(with_ext(".spvasm"), text)
+        .map_err(|e| format!("cannot dump spirv assembly: {}", e))?;
+    Ok(())
+}
+
+/// Write the dependency files requested with `depfile` and `include_graph`.
+fn write_dep_files(
+    cfg: &ShaderCompilationConfig,
+    feedback: &CompilationFeedback,
+) -> Result<(), String> {
+    let write = |path: &Path, content: String| {
+        if let Some(dir) = path.parent() {
+            std::fs::create_dir_all(dir)
+                .map_err(|e| format!("cannot create dependency file directory: {}", e))?;
+        }
+        std::fs::write(path, content)
+            .map_err(|e| format!("cannot write dependency file: {}", e))
+    };
+    let graph = DepGraph::from(feedback);
+    if let Some(depfile) = &cfg.depfile {
+        let depfile = get_base_dir().join(depfile);
+        let target = match &cfg.dump {
+            Some(dump_path) => get_base_dir().join(format!("{}.spv", dump_path)),
+            None => depfile.clone(),
+        };
+        write(&depfile, graph.to_depfile(&target.to_string_lossy()))?;
+    }
+    if let Some(include_graph) = &cfg.include_graph {
+        write(&get_base_dir().join(include_graph), graph.to_json())?;
+    }
+    Ok(())
+}
+/// Write the outputs requested besides the shader itself.
+fn write_outputs(
+    cfg: &ShaderCompilationConfig,
+    feedback: &CompilationFeedback,
+) -> Result<(), String> {
+    if let Some(dump_path) = &cfg.dump {
+        dump_spirv(Path::new(dump_path), &feedback.spv)?;
     }
-    Err("no supported backend found".to_owned())
+    write_dep_files(cfg, feedback)
 }
 
 fn build_spirv_binary(path: &Path) -> Option<Vec<u32>> {
     use std::fs::File;
     use std::io::Read;
     let mut buf = Vec::new();
-    if let Ok(mut f) = File::open(&path) {
+    if let Ok(mut f) = File::open(path) {
         if buf.len() & 3 != 0 {
             // Misaligned input.
             return None;
@@ -423,7 +541,7 @@ impl Parse for IncludedShaderSource {
         use std::ffi::OsStr;
         let path_lit = input.parse::<LitStr>()?;
         let path = Path::new(&get_base_dir())
-            .join(&path_lit.value());
+            .join(path_lit.value());
 
         if !path.exists() || !path.is_file() {
             return Err(ParseError::new(path_lit.span(),
@@ -436,32 +554,122 @@ impl Parse for IncludedShaderSource {
                 .ok_or_else(|| syn::Error::new(path_lit.span(), "invalid spirv"))?;
             CompilationFeedback {
                 spv,
-                dep_paths: vec![],
+                ..Default::default()
             }
         } else {
             let src = std::fs::read_to_string(&path)
                 .map_err(|e| syn::Error::new(path_lit.span(), e))?;
             let cfg = parse_compile_cfg(&mut input)?;
-            compile(&src, Some(path.to_string_lossy().as_ref()), &cfg)
-                .map_err(|e| ParseError::new(input.span(), e))?
+            let opts = compile_options(&cfg)?
+                .path(path.to_string_lossy());
+            let feedback = compile(&src, &opts)
+                .map_err(|e| ParseError::new(input.span(), e))?;
+            write_outputs(&cfg, &feedback)
+                .map_err(|e| ParseError::new(input.span(), e))?;
+            feedback
         };
         let rv = IncludedShaderSource(feedback);
         Ok(rv)
     }
 }
+impl Parse for LinkedShaderSource {
+    fn parse(mut input: ParseStream) -> ParseResult<Self> {
+        use std::ffi::OsStr;
+        let mut path_lits = vec![input.parse::<LitStr>()?];
+        while input.peek(Token![,]) && input.peek2(LitStr) {
+            input.parse::<Token![,]>()?;
+            path_lits.push(input.parse::<LitStr>()?);
+        }
+        let cfg = parse_compile_cfg(&mut input)?;
+        let opts = compile_options(&cfg)?;
+
+        let mut spvs = Vec::new();
+        let mut dep_paths = Vec::new();
+        let mut includes = Vec::new();
+        for path_lit in path_lits {
+            let path = Path::new(&get_base_dir())
+                .join(path_lit.value());
+            if !path.exists() || !path.is_file() {
+                return Err(ParseError::new(path_lit.span(),
+                    format!("{path} is not a valid source file", path=path_lit.value())));
+            }
+
+            let path_str = path.to_string_lossy().to_string();
+            let feedback = match path.extension().and_then(OsStr::to_str) {
+                Some("spv") => {
+                    let spv = build_spirv_binary(&path)
+                        .ok_or_else(|| syn::Error::new(path_lit.span(), "invalid spirv"))?;
+                    CompilationFeedback {
+                        spv,
+                        dep_paths: vec![path_str],
+                        ..Default::default()
+                    }
+                },
+                ext => {
+                    let src = std::fs::read_to_string(&path)
+                        .map_err(|e| syn::Error::new(path_lit.span(), e))?;
+                    // Precompiled library modules are often kept in assembly,
+                    // so we don't ask the user to say that for every path.
+                    let mut opts = opts.clone().path(path_str);
+                    if ext == Some("spvasm") {
+                        opts = opts.lang(SourceLanguage::Spvasm);
+                    }
+                    compile(&src, &opts)
+                        .map_err(|e| ParseError::new(path_lit.span(), e))?
+                },
+            };
+            spvs.push(feedback.spv);
+            dep_paths.extend(feedback.dep_paths);
+            includes.extend(feedback.includes);
+        }
+
+        let spvs = spvs.iter()
+            .map(Vec::as_slice)
+            .collect::<Vec<_>>();
+        let spv = spirv_compile_core::link::link_spirv(&spvs, &opts)
+            .map_err(|e| ParseError::new(input.span(), e))?;
+        let feedback = CompilationFeedback {
+            spv,
+            dep_paths,
+            includes,
+            ..Default::default()
+        };
+        write_outputs(&cfg, &feedback)
+            .map_err(|e| ParseError::new(input.span(), e))?;
+        let rv = LinkedShaderSource(feedback);
+        Ok(rv)
+    }
+}
 impl Parse for InlineShaderSource {
     fn parse(mut input: ParseStream) -> ParseResult<Self> {
         let src = parse_str(&mut input)?;
         let cfg = parse_compile_cfg(&mut input)?;
-        let feedback = compile(&src, None, &cfg)
+        let opts = compile_options(&cfg)?;
+        let feedback = compile(&src, &opts)
+            .map_err(|e| ParseError::new(input.span(), e))?;
+        write_outputs(&cfg, &feedback)
             .map_err(|e| ParseError::new(input.span(), e))?;
         let rv = InlineShaderSource(feedback);
         Ok(rv)
     }
 }
 
+/// Paths to the dependencies for `include_bytes!`, which resolves relative
+/// paths against the invoking source file, rather than the working directory
+/// of the compiler where relative include directories are searched.
+fn include_bytes_paths<'a, I>(dep_paths: I) -> Vec<String>
+where
+    I: IntoIterator<Item = &'a String>,
+{
+    let cwd = std::env::current_dir().unwrap_or_default();
+    dep_paths.into_iter()
+        .map(|x| cwd.join(x).to_string_lossy().into_owned())
+        .collect()
+}
+
 fn gen_token_stream(feedback: CompilationFeedback) -> TokenStream {
-    let CompilationFeedback { spv, dep_paths } = feedback;
+    let CompilationFeedback { spv, dep_paths, .. } = feedback;
+    let dep_paths = include_bytes_paths(&dep_paths);
     (quote! {
         {
             { #(let _ = include_bytes!(#dep_paths);)* }
@@ -470,6 +678,169 @@ fn gen_token_stream(feedback: CompilationFeedback) -> TokenStream {
     }).into()
 }
 
+#[cfg(feature = "cross")]
+impl Parse for IncludedCrossSource {
+    fn parse(mut input: ParseStream) -> ParseResult<Self> {
+        use std::ffi::OsStr;
+        let path_lit = input.parse::<LitStr>()?;
+        let path = Path::new(&get_base_dir())
+            .join(path_lit.value());
+
+        if !path.exists() || !path.is_file() {
+            return Err(ParseError::new(path_lit.span(),
+                format!("{path} is not a valid source file", path=path_lit.value())));
+        }
+
+        let cross_input = if path.extension() == Some(OsStr::new("spv")) {
+            let spv = build_spirv_binary(&path)
+                .ok_or_else(|| syn::Error::new(path_lit.span(), "invalid spirv"))?;
+            CrossInput::Spirv(spv)
+        } else {
+            let src = std::fs::read_to_string(&path)
+                .map_err(|e| syn::Error::new(path_lit.span(), e))?;
+            CrossInput::Text(src)
+        };
+        let cfg = parse_compile_cfg(&mut input)?;
+        let opts = compile_options(&cfg)?
+            .path(path.to_string_lossy());
+        let rv = IncludedCrossSource(CrossShaderSource {
+            input: cross_input,
+            opts,
+            cfg,
+        });
+        Ok(rv)
+    }
+}
+#[cfg(feature = "cross")]
+impl Parse for InlineCrossSource {
+    fn parse(mut input: ParseStream) -> ParseResult<Self> {
+        let src = parse_str(&mut input)?;
+        let cfg = parse_compile_cfg(&mut input)?;
+        let opts = compile_options(&cfg)?;
+        let rv = InlineCrossSource(CrossShaderSource {
+            input: CrossInput::Text(src),
+            opts,
+            cfg,
+        });
+        Ok(rv)
+    }
+}
+
+#[cfg(feature = "manifest")]
+impl Parse for ManifestShaders {
+    fn parse(input: ParseStream) -> ParseResult<Self> {
+        let path_lit = input.parse::<LitStr>()?;
+        let path = get_base_dir().join(path_lit.value());
+        let manifest = spirv_compile_core::manifest::load_manifest(&path)
+            .map_err(|e| ParseError::new(path_lit.span(), e))?;
+        let compiler = Compiler::new()
+            .map_err(|e| ParseError::new(path_lit.span(), e))?;
+
+        // Report all the shaders failed to compile at once.
+        let mut err: Option<ParseError> = None;
+        let mut shaders = Vec::new();
+        for shader in manifest {
+            let feedback = compiler.compile_file(&shader.path, &shader.opts)
+                .and_then(reject_warnings);
+            match feedback {
+                Ok(feedback) => shaders.push((shader, feedback)),
+                Err(e) => {
+                    let e = ParseError::new(path_lit.span(), format!("{}: cannot compile \
+                        shader `{}`:\n{}", shader.location, shader.name, e));
+                    match &mut err {
+                        Some(err) => err.combine(e),
+                        None => err = Some(e),
+                    }
+                },
+            }
+        }
+        if let Some(err) = err {
+            return Err(err);
+        }
+        let rv = ManifestShaders {
+            manifest_path: path.to_string_lossy().into_owned(),
+            shaders,
+        };
+        Ok(rv)
+    }
+}
+
+#[cfg(feature = "manifest")]
+fn gen_manifest_token_stream(manifest: ManifestShaders) -> TokenStream {
+    use std::collections::BTreeSet;
+    use quote::format_ident;
+    let ManifestShaders { manifest_path, shaders } = manifest;
+    let dep_paths = shaders.iter()
+        .flat_map(|(_, feedback)| include_bytes_paths(&feedback.dep_paths))
+        .collect::<BTreeSet<_>>();
+    let consts = shaders.iter().map(|(shader, feedback)| {
+        let name = format_ident!("{}", shader.name);
+        let doc = format!(" Entry point `{}` of `{}`.", feedback.entry,
+            shader.path.file_name().unwrap_or_default().to_string_lossy());
+        let spv = &feedback.spv;
+        quote! {
+            #[doc = #doc]
+            pub static #name: &[u32] = &[#(#spv),*];
+        }
+    });
+    (quote! {
+        const _: &[u8] = include_bytes!(#manifest_path);
+        #(const _: &[u8] = include_bytes!(#dep_paths);)*
+        #(#consts)*
+    }).into()
+}
+
+fn gen_disasm_token_stream(feedback: CompilationFeedback) -> TokenStream {
+    let CompilationFeedback { spv, dep_paths, .. } = feedback;
+    let dep_paths = include_bytes_paths(&dep_paths);
+    let text = match spirv_compile_core::disassemble_spirv(&spv) {
+        Ok(x) => x,
+        Err(e) => return ParseError::new(proc_macro::Span::call_site().into(), e)
+            .to_compile_error()
+            .into(),
+    };
+    (quote! {
+        {
+            { #(let _ = include_bytes!(#dep_paths);)* }
+            #text
+        }
+    }).into()
+}
+
+#[cfg(feature = "cross")]
+fn gen_cross_token_stream(
+    src: CrossShaderSource,
+    target: backends::naga_cross::CrossTarget,
+) -> TokenStream {
+    use backends::naga_cross::{translate, CrossSource};
+    let CrossShaderSource { input, opts, cfg } = src;
+    let cross_src = match &input {
+        CrossInput::Text(x) => CrossSource::Text(x),
+        CrossInput::Spirv(x) => CrossSource::Spirv(x),
+    };
+    let out = translate(cross_src, &opts, &cfg, target)
+        .and_then(|(text, feedback)| {
+            if cfg.dump.is_some() && feedback.spv.is_empty() {
+                return Err("`dump` is not supported for WGSL sources, which are \
+                    translated without being compiled to SPIR-V".to_owned());
+            }
+            write_outputs(&cfg, &feedback)?;
+            Ok((text, include_bytes_paths(&feedback.dep_paths)))
+        });
+    let (text, dep_paths) = match out {
+        Ok(x) => x,
+        Err(e) => return ParseError::new(proc_macro::Span::call_site().into(), e)
+            .to_compile_error()
+            .into(),
+    };
+    (quote! {
+        {
+            { #(let _ = include_bytes!(#dep_paths);)* }
+            #text
+        }
+    }).into()
+}
+
 /// Compile inline shader source and embed the SPIR-V binary word sequence.
 /// Returns a `&'static [u32]`.
 #[proc_macro]
@@ -484,3 +855,114 @@ pub fn include_spirv(tokens: TokenStream) -> TokenStream {
     let IncludedShaderSource(feedback) = parse_macro_input!(tokens as IncludedShaderSource);
     gen_token_stream(feedback)
 }
+/// Compile multiple external shader sources or precompiled SPIR-V binaries,
+/// link them into a single module and embed the SPIR-V binary word sequence.
+/// Only the entry point named by `entry` is kept in the linked module. Returns
+/// a `&'static [u32]`.
+#[proc_macro]
+pub fn link_spirv(tokens: TokenStream) -> TokenStream {
+    let LinkedShaderSource(feedback) = parse_macro_input!(tokens as LinkedShaderSource);
+    gen_token_stream(feedback)
+}
+/// Compile inline shader source and embed the disassembly of the SPIR-V
+/// binary. Returns a `&'static str`.
+#[proc_macro]
+pub fn inline_spirv_disasm(tokens: TokenStream) -> TokenStream {
+    let InlineShaderSource(feedback) = parse_macro_input!(tokens as InlineShaderSource);
+    gen_disasm_token_stream(feedback)
+}
+/// Compile external shader source and embed the disassembly of the SPIR-V
+/// binary. Returns a `&'static str`.
+#[proc_macro]
+pub fn include_spirv_disasm(tokens: TokenStream) -> TokenStream {
+    let IncludedShaderSource(feedback) = parse_macro_input!(tokens as IncludedShaderSource);
+    gen_disasm_token_stream(feedback)
+}
+/// Compile all the shaders declared in a shader manifest, and generate a
+/// `pub static` shader constant of type `&'static [u32]` for each of them.
+#[cfg(feature = "manifest")]
+#[proc_macro]
+pub fn shader_manifest(tokens: TokenStream) -> TokenStream {
+    let manifest = parse_macro_input!(tokens as ManifestShaders);
+    gen_manifest_token_stream(manifest)
+}
+/// Translate inline shader source into Metal Shading Language and embed the translated
+/// source. Returns a `&'static str`.
+#[cfg(feature = "cross")]
+#[proc_macro]
+pub fn inline_msl(tokens: TokenStream) -> TokenStream {
+    let InlineCrossSource(src) = parse_macro_input!(tokens as InlineCrossSource);
+    gen_cross_token_stream(src, backends::naga_cross::CrossTarget::Msl)
+}
+/// Translate external shader source or SPIR-V binary into Metal Shading Language and embed
+/// the translated source. Returns a `&'static str`.
+#[cfg(feature = "cross")]
+#[proc_macro]
+pub fn include_msl(tokens: TokenStream) -> TokenStream {
+    let IncludedCrossSource(src) = parse_macro_input!(tokens as IncludedCrossSource);
+    gen_cross_token_stream(src, backends::naga_cross::CrossTarget::Msl)
+}
+/// Translate inline shader source into HLSL and embed the translated
+/// source. Returns a `&'static str`.
+#[cfg(feature = "cross")]
+#[proc_macro]
+pub fn inline_hlsl(tokens: TokenStream) -> TokenStream {
+    let InlineCrossSource(src) = parse_macro_input!(tokens as InlineCrossSource);
+    gen_cross_token_stream(src, backends::naga_cross::CrossTarget::Hlsl)
+}
+/// Translate external shader source or SPIR-V binary into HLSL and embed
+/// the translated source. Returns a `&'static str`.
+#[cfg(feature = "cross")]
+#[proc_macro]
+pub fn include_hlsl(tokens: TokenStream) -> TokenStream {
+    let IncludedCrossSource(src) = parse_macro_input!(tokens as IncludedCrossSource);
+    gen_cross_token_stream(src, backends::naga_cross::CrossTarget::Hlsl)
+}
+/// Translate inline shader source into desktop GLSL and embed the translated
+/// source. Returns a `&'static str`.
+#[cfg(feature = "cross")]
+#[proc_macro]
+pub fn inline_glsl(tokens: TokenStream) -> TokenStream {
+    let InlineCrossSource(src) = parse_macro_input!(tokens as InlineCrossSource);
+    gen_cross_token_stream(src, backends::naga_cross::CrossTarget::Glsl)
+}
+/// Translate external shader source or SPIR-V binary into desktop GLSL and embed
+/// the translated source. Returns a `&'static str`.
+#[cfg(feature = "cross")]
+#[proc_macro]
+pub fn include_glsl(tokens: TokenStream) -> TokenStream {
+    let IncludedCrossSource(src) = parse_macro_input!(tokens as IncludedCrossSource);
+    gen_cross_token_stream(src, backends::naga_cross::CrossTarget::Glsl)
+}
+/// Translate inline shader source into GLSL ES and embed the translated
+/// source. Returns a `&'static str`.
+#[cfg(feature = "cross")]
+#[proc_macro]
+pub fn inline_glsl_es(tokens: TokenStream) -> TokenStream {
+    let InlineCrossSource(src) = parse_macro_input!(tokens as InlineCrossSource);
+    gen_cross_token_stream(src, backends::naga_cross::CrossTarget::GlslEs)
+}
+/// Translate external shader source or SPIR-V binary into GLSL ES and embed
+/// the translated source. Returns a `&'static str`.
+#[cfg(feature = "cross")]
+#[proc_macro]
+pub fn include_glsl_es(tokens: TokenStream) -> TokenStream {
+    let IncludedCrossSource(src) = parse_macro_input!(tokens as IncludedCrossSource);
+    gen_cross_token_stream(src, backends::naga_cross::CrossTarget::GlslEs)
+}
+/// Translate inline shader source into WGSL and embed the translated
+/// source. Returns a `&'static str`.
+#[cfg(feature = "cross")]
+#[proc_macro]
+pub fn inline_wgsl(tokens: TokenStream) -> TokenStream {
+    let InlineCrossSource(src) = parse_macro_input!(tokens as InlineCrossSource);
+    gen_cross_token_stream(src, backends::naga_cross::CrossTarget::Wgsl)
+}
+/// Translate external shader source or SPIR-V binary into WGSL and embed
+/// the translated source. Returns a `&'static str`.
+#[cfg(feature = "cross")]
+#[proc_macro]
+pub fn include_wgsl(tokens: TokenStream) -> TokenStream {
+    let IncludedCrossSource(src) = parse_macro_input!(tokens as IncludedCrossSource);
+    gen_cross_token_stream(src, backends::naga_cross::CrossTarget::Wgsl)
+}
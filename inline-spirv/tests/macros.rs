@@ -0,0 +1,96 @@
+//! Invocations of the macros compiling shaders at build time. The shaders are
+//! shared with the tests of `jit-spirv` in `tests/shaders` of the workspace.
+use inline_spirv::{inline_spirv, include_spirv};
+
+const SPIRV_VERSION_1_0: u32 = 0x0001_0000;
+const SPIRV_VERSION_1_3: u32 = 0x0001_0300;
+
+/// Every file the shader depends on is embedded with `include_bytes!`, so the
+/// crate is rebuilt whenever any of them changes. Invalid paths fail the build,
+/// so it's enough that these compile: includes next to the shader file, and
+/// includes in directories relative to the working directory of the compiler.
+#[cfg(feature = "glsl")]
+#[test]
+fn includes_are_tracked_as_dependencies() {
+    let spv: &[u32] = include_spirv!("../tests/shaders/lit.frag", D LIGHT_COUNT="2");
+    assert_eq!(spv[0], 0x07230203);
+
+    let spv: &[u32] = inline_spirv!(r#"
+        #version 450
+        #include <light.glsl>
+        layout(location = 0) out vec4 color;
+        uniform sampler2D albedo;
+        void main() {
+            color = texture(albedo, vec2(0.0)) * light(LIGHT_COUNT);
+        }
+    "#, glsl, frag, I "tests/shaders", D LIGHT_COUNT="2", entry="main",
+        vulkan1_0, spirq1_3, max_perf, no_debug, auto_bind, binding_base(texture=4));
+    // `spirq1_3` compiles for Vulkan 1.1.
+    assert_eq!(spv[1], SPIRV_VERSION_1_3);
+}
+
+#[cfg(feature = "wgsl")]
+#[test]
+fn target_environments_reset_the_spirv_version() {
+    let spv: &[u32] = include_spirv!("../tests/shaders/quad.wgsl", wgsl, frag, entry="fs_main",
+        vulkan1_0, spirq1_3, no_debug, no_y_flip);
+    assert_eq!(spv[1], SPIRV_VERSION_1_3);
+    // The target environment resets the SPIR-V version forced before it.
+    let spv: &[u32] = inline_spirv!(r#"
+        @vertex
+        fn vs_main() -> @builtin(position) vec4<f32> { return vec4<f32>(0.0); }
+    "#, wgsl, vert, entry="vs_main", spirq1_3, vulkan1_0);
+    assert_eq!(spv[1], SPIRV_VERSION_1_0);
+}
+
+#[cfg(all(feature = "cross", feature = "wgsl"))]
+#[test]
+fn cross_compilation_writes_dep_files() {
+    use inline_spirv::include_msl;
+    let msl: &str = include_msl!("../tests/shaders/quad.wgsl", wgsl, frag, entry="fs_main",
+        depfile="target/tests/quad.metal.d", include_graph="target/tests/quad.metal.json");
+    assert!(msl.contains("fs_main"));
+    let depfile = std::fs::read_to_string("target/tests/quad.metal.d").unwrap();
+    assert!(depfile.contains("quad.wgsl"), "{}", depfile);
+    let graph = std::fs::read_to_string("target/tests/quad.metal.json").unwrap();
+    assert!(graph.contains("quad.wgsl"), "{}", graph);
+}
+
+#[cfg(feature = "wgsl")]
+#[test]
+fn compiled_shaders_are_disassembled() {
+    use inline_spirv::inline_spirv_disasm;
+    let text: &str = inline_spirv_disasm!(r#"
+        @fragment
+        fn fs_main() -> @location(0) vec4<f32> { return vec4<f32>(1.0); }
+    "#, wgsl, frag, entry="fs_main");
+    let entry_point = text.lines()
+        .find(|x| x.contains("OpEntryPoint"))
+        .unwrap_or_else(|| panic!("{}", text));
+    assert!(entry_point.contains("Fragment"), "{}", entry_point);
+    assert!(entry_point.contains("\"fs_main\""), "{}", entry_point);
+}
+
+#[cfg(feature = "wgsl")]
+#[test]
+fn dump_writes_the_binary_and_its_assembly() {
+    let spv: &[u32] = include_spirv!("../tests/shaders/quad.wgsl", wgsl, frag, entry="fs_main",
+        dump="target/tests/quad");
+    let bytes = std::fs::read("target/tests/quad.spv").unwrap();
+    let words = bytes.chunks_exact(4)
+        .map(|x| u32::from_le_bytes([x[0], x[1], x[2], x[3]]))
+        .collect::<Vec<_>>();
+    assert_eq!(words, spv);
+    let text = std::fs::read_to_string("target/tests/quad.spvasm").unwrap();
+    assert!(text.contains("OpEntryPoint Fragment"), "{}", text);
+}
+
+#[test]
+fn spvasm_header_fields_are_written() {
+    let spv: &[u32] = inline_spirv!(r#"
+        OpCapability Shader
+        OpMemoryModel Logical GLSL450
+    "#, spvasm, generator=7, bound=32);
+    assert_eq!(spv[2], 7);
+    assert_eq!(spv[3], 32);
+}
@@ -1,2 +0,0 @@
-pub mod shaderc;
-pub mod naga;
@@ -6,11 +6,12 @@
 //!
 //! ## Source Language
 //!
-//! `jit-spirv` currently support three source languages:
+//! `jit-spirv` currently support four source languages:
 //!
 //! - `glsl`: The shader source is in GLSL (enabled by default);
 //! - `hlsl`: The shader source is in HLSL (enabled by default);
-//! - `wgsl`: The shader source is in WGSL.
+//! - `wgsl`: The shader source is in WGSL;
+//! - `spvasm`: The shader source is in SPIR-V assembly (enabled by default).
 //!
 //! The experimental WGSL support for WebGPU is available when `wgsl` feature is
 //! enabled, but currently you have to compile with a nightly toolchain. Limited
@@ -52,7 +53,7 @@
 //! - `min_size`: Optimize for the minimal output size;
 //! - `max_perf`: Optimize for the best performance;
 //! - `no_debug`: Strip off all the debug information (don't do this if you want
-//! to reflect the SPIR-V and get variable names).
+//!   to reflect the SPIR-V and get variable names).
 //!
 //! ## Compiler Definition
 //!
@@ -64,6 +65,20 @@
 //!     D LIGHTMAP_COUNT="2");
 //! ```
 //!
+//! Values of definitions and include directories can also be any expressions
+//! evaluated at runtime. Definition values are converted with `ToString`. You
+//! can also give a list of definitions with `defines=expr`, where `expr` is an
+//! `IntoIterator<Item=(K, Option<V>)>`, and a list of include directories with
+//! `include_dirs=expr`:
+//!
+//! ```ignore
+//! jit_spirv!(glsl_source, vert,
+//!     D LIGHTMAP_COUNT=material.lightmaps.len(),
+//!     I asset_dir.join("shaders"),
+//!     defines=material.defines.iter().map(|(k, v)| (k, v.as_ref())),
+//!     include_dirs=&mod_shader_dirs);
+//! ```
+//!
 //! You can request a specific version of target environment:
 //! - `vulkan1_0` for Vulkan 1.0 (default, supports SPIR-V 1.0);
 //! - `vulkan1_1` for Vulkan 1.1 (supports SPIR-V 1.3);
@@ -71,10 +86,57 @@
 //! - `opengl4_5` for OpenGL 4.5 core profile.
 //! - `webgpu` for WebGPU.
 //!
+//! The SPIR-V version can also be forced with `spirq1_0` to `spirq1_6` after
+//! the target environment. GLSL, HLSL and WGSL sources are then compiled for
+//! the Vulkan version supporting it, e.g., `spirq1_3` for Vulkan 1.1.
+//!
 //! Of course once you started to use macro is basically means that you are
 //! getting so dynamic that this little crate might not be enough. Then it might
 //! be a good time to build your own shader compilation pipeline!
 //!
+//! ## GLSL Version and Resource Limits
+//!
+//! For GLSL sources without a `#version` directive, or to override it, you can
+//! force the GLSL version and profile with `glsl_version=450` and
+//! `profile=core`, `profile=compatibility` or `profile=es`. If the profile is
+//! omitted, `es` is assumed for ES versions (100, 300, 310 and 320) and `core`
+//! for versions since 150.
+//!
+//! Shaders can be checked against the resource limits of your target devices
+//! with `limit(...)`, and/or by loading a
+//! [glslang resource limit file](https://github.com/KhronosGroup/glslang/blob/main/glslang/ResourceLimits/ResourceLimits.cpp)
+//! (read at runtime) with `limits_file`. Limits in `limit(...)` take precedence:
+//!
+//! ```ignore
+//! jit_spirv!(glsl_source, comp,
+//!     glsl_version=450, profile=core,
+//!     limits_file="limits/mobile.conf",
+//!     limit(MaxComputeWorkGroupSizeX=1024, MaxClipDistances=8));
+//! ```
+//!
+//! ## Include Resolution
+//!
+//! `#include`s are read from the filesystem unless you give an
+//! `Arc<impl jit_spirv::IncludeResolver>` with `resolver=expr`, e.g., to read
+//! them from asset archives:
+//!
+//! ```ignore
+//! jit_spirv!(glsl_source, frag, resolver=archive_resolver.clone());
+//! ```
+//!
+//! ## Compilation Cache
+//!
+//! Compiled shaders can be reused from a `jit_spirv::ShaderCache`, so the same
+//! shader is not compiled again in the same run, or in later runs if the cache
+//! is backed by a directory:
+//!
+//! ```ignore
+//! static CACHE: Lazy<ShaderCache> = Lazy::new(|| {
+//!     ShaderCache::new(256).with_dir("target/shader-cache")
+//! });
+//! jit_spirv!(glsl_source, frag, cache=&*CACHE);
+//! ```
+//!
 //! ## Descriptor Auto-binding
 //!
 //! If you are just off your work being tooooo tired to specify the descriptor
@@ -92,244 +154,341 @@
 //! However, if you don't have any automated reflection tool to get the actual
 //! binding points, it's not recommended to use this.
 //!
+//! ## HLSL Options
+//!
+//! HLSL sources can be compiled with some extra options:
+//!
+//! - `shader_model="6.2"`: Define the `__SHADER_TARGET_MAJOR` and
+//!   `__SHADER_TARGET_MINOR` macros of the given shader model, as DXC does. The
+//!   HLSL frontend of shaderc has no notion of shader models, so the code
+//!   generation is not affected. Only shader models 4.0, 4.1, 5.0, 5.1 and 6.0
+//!   to 6.8 are accepted;
+//! - `hlsl_16bit_types`: Enable 16-bit types like `half` and `min16float`
+//!   (requires shader model 6.2 or above if one is specified);
+//! - `hlsl_offsets`: Use HLSL packing rules instead of GLSL rules for buffer
+//!   member offsets;
+//! - `hlsl_io_mapping`: Use HLSL IO mappings for bindings;
+//! - `hlsl_functionality1`: Emit the `SPV_GOOGLE_hlsl_functionality1`
+//!   extension, which keeps semantic strings for reflection;
+//! - `hlsl_explicit_binding("t0", 0, 1)`: Assign the descriptor set and binding
+//!   of a register explicitly. This can be specified multiple times;
+//! - `hlsl_explicit_binding_for_stage(frag, "t0", 0, 1)`: Same as above but
+//!   only effective for the given stage.
+//!
+//! ## Descriptor Binding Base
+//!
+//! Bindings of each kind of resource can be shifted by a base binding number,
+//! which applies to automatically assigned bindings and HLSL registers alike.
+//! This is useful to compile HLSL code written for D3D register conventions to
+//! deterministic Vulkan bindings where `t#`, `s#`, `b#` and `u#` registers
+//! don't collide:
+//!
+//! ```ignore
+//! jit_spirv!(hlsl_source, hlsl, frag,
+//!     binding_base(texture=0, sampler=16, buffer=32, uav=48),
+//!     binding_base_for_stage(vert, buffer=64));
+//! ```
+//!
+//! The resource kinds are:
+//!
+//! - `image`: Images and image buffers;
+//! - `sampler`: Pure samplers (`s#` registers in HLSL);
+//! - `texture`: Sampled textures in GLSL and shader resource views in HLSL
+//!   (`t#` registers);
+//! - `buffer`: Uniform buffers in GLSL and cbuffers in HLSL (`b#` registers);
+//! - `storage_buffer`: Shader storage buffers in GLSL;
+//! - `uav`: Unordered access views in HLSL (`u#` registers).
+//!
 //! ## Flip-Y for WebGPU
 //!
 //! If you intend to compile WGSL for a WebGPU backend, `naga` by default
 //! inverts the Y-axis due to the discrepancy in NDC (Normalized Device
 //! Coordinates) between WebGPU and Vulkan. If such correction is undesired, you
 //! can opt out with `no_y_flip`.
+//!
+//! ## SPIR-V Assembly Header
+//!
+//! The SPIR-V module header of assembled `spvasm` sources is taken from the
+//! header comments at the beginning of the source, just as `spirv-dis` prints
+//! them. The SPIR-V version can be forced with `spirq1_0` to `spirq1_6`; if
+//! neither is given, the version supported by the target environment is used.
+//! You can also specify the generator magic number with `generator=<u32>`, and
+//! an ID bound with `bound=<u32>`, which override the header comments:
+//!
+//! ```ignore
+//! jit_spirv!(spvasm_source, spvasm, spirq1_3, generator=0x000d000a);
+//! ```
+//!
+//! ## Options Shared with `inline-spirv`
+//!
+//! `jit-spirv` and `inline-spirv` parse the options with the same grammar from
+//! `spirv-compile-core`, so a shader can be moved between runtime and build
+//! time without changing its options. Options only meaningful at build time,
+//! i.e., `dump`, `out_version`, `depfile` and `include_graph`, are rejected by
+//! `jit_spirv!`.
+//!
+//! ## Shader Files
+//!
+//! `jit_include_spirv!` takes the path to a shader file instead of the source,
+//! and reads the file at runtime. The source language and the shader stage are
+//! inferred from the file extensions if they are not given, e.g., `.frag` is a
+//! GLSL fragment shader and `.vert.hlsl` is an HLSL vertex shader. The path is
+//! evaluated at runtime, and relative `#include`s are resolved against it:
+//!
+//! ```ignore
+//! jit_include_spirv!(asset_dir.join("shaders/water.frag"), auto_bind);
+//! ```
+//!
+//! ## Crate Path
+//!
+//! The generated code refers to the items of `jit-spirv`. The crate is found in
+//! the dependencies of your crate even if it's renamed in `Cargo.toml`; but if
+//! `jit-spirv` is only reachable through a re-export of another crate, you have
+//! to give the path with `crate=<path>`:
+//!
+//! ```ignore
+//! jit_spirv!(source, frag, crate=engine::jit_spirv);
+//! ```
 extern crate proc_macro;
 
-mod backends;
 
-#[cfg(not(any(feature = "shaderc", feature = "naga")))]
+#[cfg(not(any(feature = "shaderc", feature = "naga", feature = "spvasm")))]
 compile_error!("no compiler backend enabled; please specify at least one of \
-    the following input source features: `glsl`, `hlsl`, `wgsl`");
+    the following input source features: `glsl`, `hlsl`, `wgsl`, `spvasm`");
 
 use proc_macro::TokenStream;
-use proc_macro2::Span;
 use syn::parse::{Parse, ParseStream, Result as ParseResult, Error as ParseError};
-use syn::{parse_macro_input, Ident, LitStr, Token, Expr};
+use syn::{parse_macro_input, Expr};
+use spirv_compile_core::syntax::{parse_compile_cfg, str_lit_value, ShaderCompilationConfig};
+use spirv_compile_core::{ShaderStage, SourceLanguage};
 
-#[derive(Clone, Copy)]
-enum InputSourceLanguage {
-    Unknown,
-    Glsl,
-    Hlsl,
-    Wgsl,
-}
-#[derive(Clone, Copy)]
-enum TargetSpirvVersion {
-    Spirv1_0,
-    #[allow(dead_code)]
-    Spirv1_1,
-    #[allow(dead_code)]
-    Spirv1_2,
-    Spirv1_3,
-    #[allow(dead_code)]
-    Spirv1_4,
-    Spirv1_5,
-}
-#[derive(Clone, Copy)]
-enum TargetEnvironmentType {
-    Vulkan,
-    OpenGL,
-    WebGpu,
-}
-#[derive(Clone, Copy)]
-enum OptimizationLevel {
-    MinSize,
-    MaxPerformance,
-    None,
-}
-#[derive(Clone, Copy)]
-enum ShaderKind {
-    Unknown,
+struct JitSpirv(TokenStream);
 
-    Vertex,
-    TesselationControl,
-    TesselationEvaluation,
-    Geometry,
-    Fragment,
-    Compute,
-    // Mesh Pipeline
-    Mesh,
-    Task,
-    // Ray-tracing Pipeline
-    RayGeneration,
-    Intersection,
-    AnyHit,
-    ClosestHit,
-    Miss,
-    Callable,
+/// Path to the `jit_spirv` crate in the generated code. Unless it's given with
+/// `crate=path`, it's looked up in the dependencies of the calling crate, so a
+/// renamed dependency works as well.
+fn crate_path(cfg: &ShaderCompilationConfig) -> proc_macro2::TokenStream {
+    use proc_macro_crate::{crate_name, FoundCrate};
+    use quote::{format_ident, quote};
+    if let Some(path) = &cfg.crate_path {
+        return quote!(#path);
+    }
+    match crate_name("jit-spirv") {
+        Ok(FoundCrate::Name(name)) => {
+            let name = format_ident!("{}", name);
+            quote!(::#name)
+        },
+        // The examples and tests of `jit-spirv` itself, or a re-export that
+        // should have been given with `crate=path`.
+        Ok(FoundCrate::Itself) | Err(_) => quote!(::jit_spirv),
+    }
 }
-
-struct ShaderCompilationConfig {
-    path: Option<String>,
-    lang: InputSourceLanguage,
-    incl_dirs: Vec<String>,
-    defs: Vec<(String, Option<String>)>,
-    spv_ver: TargetSpirvVersion,
-    env_ty: TargetEnvironmentType,
-    entry: String,
-    optim_lv: OptimizationLevel,
-    debug: bool,
-    kind: ShaderKind,
-    auto_bind: bool,
-    // Backend specific.
-    #[cfg(feature = "naga")]
-    y_flip: bool,
+/// Path to a variant of an enum re-exported by `jit_spirv`. The variants are
+/// named the same as in `spirv_compile_core`.
+fn variant_tokens<T: std::fmt::Debug>(
+    krate: &proc_macro2::TokenStream,
+    ty: &str,
+    x: T,
+) -> proc_macro2::TokenStream {
+    use quote::{format_ident, quote};
+    let ty = format_ident!("{}", ty);
+    let variant = format_ident!("{}", format!("{:?}", x));
+    quote!(#krate::#ty::#variant)
 }
-impl Default for ShaderCompilationConfig {
-    fn default() -> Self {
-        ShaderCompilationConfig {
-            path: None,
-            lang: InputSourceLanguage::Unknown,
-            incl_dirs: Vec::new(),
-            defs: Vec::new(),
-            spv_ver: TargetSpirvVersion::Spirv1_0,
-            env_ty: TargetEnvironmentType::Vulkan,
-            entry: "main".to_owned(),
-            optim_lv: OptimizationLevel::None,
-            debug: true,
-            kind: ShaderKind::Unknown,
-            auto_bind: false,
-
-            #[cfg(feature = "naga")]
-            y_flip: true,
-        }
+fn optional_stage_tokens(
+    krate: &proc_macro2::TokenStream,
+    stage: Option<ShaderStage>,
+) -> proc_macro2::TokenStream {
+    use quote::quote;
+    match stage {
+        Some(stage) => {
+            let stage = variant_tokens(krate, "ShaderStage", stage);
+            quote!(Some(#stage))
+        },
+        None => quote!(None),
     }
 }
 
-struct JitSpirv(TokenStream);
-
-#[inline]
-fn parse_str(input: &mut ParseStream) -> ParseResult<String> {
-    input.parse::<LitStr>()
-        .map(|x| x.value())
+fn is_str_lit(expr: &Expr) -> bool {
+    str_lit_value(expr).is_some()
 }
-#[inline]
-fn parse_ident(input: &mut ParseStream) -> ParseResult<String> {
-    input.parse::<Ident>()
-        .map(|x| x.to_string())
+/// Whether any option is evaluated at runtime, so the options cannot be built
+/// once for all.
+fn has_runtime_options(cfg: &ShaderCompilationConfig) -> bool {
+    cfg.resolver.is_some() ||
+        !cfg.incl_dir_lists.is_empty() ||
+        !cfg.def_lists.is_empty() ||
+        !cfg.incl_dirs.iter().all(is_str_lit) ||
+        !cfg.defs.iter().all(|(_, v)| v.iter().all(is_str_lit))
 }
 
-fn parse_compile_cfg(
-    input: &mut ParseStream
-) -> ParseResult<ShaderCompilationConfig> {
-    let mut cfg = ShaderCompilationConfig::default();
-    while !input.is_empty() {
-        use syn::Error;
-        // Capture comma and collon; they are for readability.
-        input.parse::<Token![,]>()?;
-        let k = if let Ok(k) = input.parse::<Ident>() { k } else { break };
-        match &k.to_string() as &str {
-            "path" => {
-                input.parse::<Token![,]>()?;
-                cfg.path = Some(parse_str(input)?);
-            },
-
-            "glsl" => cfg.lang = InputSourceLanguage::Glsl,
-            "hlsl" => {
-                cfg.lang = InputSourceLanguage::Hlsl;
-                // HLSL might be illegal if optimization is disabled. Not sure,
-                // `glslangValidator` said this.
-                cfg.optim_lv = OptimizationLevel::MaxPerformance;
-            },
-            "wgsl" => cfg.lang = InputSourceLanguage::Wgsl,
-
-            "vert" => cfg.kind = ShaderKind::Vertex,
-            "tesc" => cfg.kind = ShaderKind::TesselationControl,
-            "tese" => cfg.kind = ShaderKind::TesselationEvaluation,
-            "geom" => cfg.kind = ShaderKind::Geometry,
-            "frag" => cfg.kind = ShaderKind::Fragment,
-            "comp" => cfg.kind = ShaderKind::Compute,
-            "mesh" => cfg.kind = ShaderKind::Mesh,
-            "task" => cfg.kind = ShaderKind::Task,
-            "rgen" => cfg.kind = ShaderKind::RayGeneration,
-            "rint" => cfg.kind = ShaderKind::Intersection,
-            "rahit" => cfg.kind = ShaderKind::AnyHit,
-            "rchit" => cfg.kind = ShaderKind::ClosestHit,
-            "rmiss" => cfg.kind = ShaderKind::Miss,
-            "rcall" => cfg.kind = ShaderKind::Callable,
-
-            "I" => {
-                cfg.incl_dirs.push(parse_str(input)?)
-            },
-            "D" => {
-                let k = parse_ident(input)?;
-                let v = if input.parse::<Token![=]>().is_ok() {
-                    Some(parse_str(input)?)
-                } else { None };
-                cfg.defs.push((k, v));
-            },
-
-            "entry" => {
-                if input.parse::<Token![=]>().is_ok() {
-                    cfg.entry = parse_str(input)?.to_owned();
-                }
-            }
-
-            "min_size" => cfg.optim_lv = OptimizationLevel::MinSize,
-            "max_perf" => cfg.optim_lv = OptimizationLevel::MaxPerformance,
-
-            "no_debug" => cfg.debug = false,
+/// Generate the code building `jit_spirv::CompileOptions` from the macro
+/// options. The backends are run by `jit_spirv::Compiler` at runtime.
+fn generate_options_code(
+    cfg: &ShaderCompilationConfig,
+    krate: &proc_macro2::TokenStream,
+) -> Result<proc_macro2::TokenStream, String> {
+    use quote::quote;
+    let mut out = quote!(#krate::CompileOptions::new());
 
-            "vulkan" | "vulkan1_0" => {
-                cfg.env_ty = TargetEnvironmentType::Vulkan;
-                cfg.spv_ver = TargetSpirvVersion::Spirv1_0;
-            },
-            "vulkan1_1" => {
-                cfg.env_ty = TargetEnvironmentType::Vulkan;
-                cfg.spv_ver = TargetSpirvVersion::Spirv1_3;
-            },
-            "vulkan1_2" => {
-                cfg.env_ty = TargetEnvironmentType::Vulkan;
-                cfg.spv_ver = TargetSpirvVersion::Spirv1_5;
-            },
-            "opengl" | "opengl4_5" => {
-                cfg.env_ty = TargetEnvironmentType::OpenGL;
-                cfg.spv_ver = TargetSpirvVersion::Spirv1_0;
-            },
-            "webgpu" => {
-                cfg.env_ty = TargetEnvironmentType::WebGpu;
-                cfg.spv_ver = TargetSpirvVersion::Spirv1_0;
-            }
+    let is_valid = match cfg.lang {
+        None => cfg!(any(feature = "shaderc", feature = "naga", feature = "spvasm")),
+        Some(SourceLanguage::Spvasm) => cfg!(feature = "spvasm"),
+        Some(SourceLanguage::Glsl) | Some(SourceLanguage::Hlsl) => cfg!(feature = "shaderc"),
+        Some(SourceLanguage::Wgsl) => cfg!(feature = "naga"),
+    };
+    if !is_valid {
+        return Err("cannot find a proper shader compiler backend".to_owned());
+    }
+    if cfg.dump.is_some() {
+        return Err("`dump` is only supported at build time with `inline-spirv`".to_owned());
+    }
+    if cfg.out_version.is_some() {
+        return Err("`out_version` is only supported at build time with `inline-spirv`".to_owned());
+    }
+    if cfg.depfile.is_some() || cfg.include_graph.is_some() {
+        return Err("`depfile` and `include_graph` are only supported at build time with \
+            `inline-spirv`".to_owned());
+    }
 
-            "auto_bind" => cfg.auto_bind = true,
+    if let Some(path) = &cfg.path {
+        out.extend(quote!(.path(#path)));
+    }
+    if let Some(lang) = cfg.lang {
+        let lang = variant_tokens(krate, "SourceLanguage", lang);
+        out.extend(quote!(.lang(#lang)));
+    }
+    if let Some(stage) = cfg.stage {
+        let stage = variant_tokens(krate, "ShaderStage", stage);
+        out.extend(quote!(.stage(#stage)));
+    }
+    for incl_dir in cfg.incl_dirs.iter() {
+        out.extend(quote!(.include_dir(#incl_dir)));
+    }
+    for incl_dir_list in cfg.incl_dir_lists.iter() {
+        out.extend(quote!(.include_dirs(#incl_dir_list)));
+    }
+    if let Some(resolver) = &cfg.resolver {
+        out.extend(quote!(.shared_resolver(#resolver)));
+    }
+    for (k, v) in cfg.defs.iter() {
+        out.extend(match v {
+            Some(v) if is_str_lit(v) => quote!(.define(#k, Some(#v))),
+            Some(v) => quote!(.define(#k, Some(&::std::string::ToString::to_string(&(#v))))),
+            None => quote!(.define(#k, None)),
+        });
+    }
+    for def_list in cfg.def_lists.iter() {
+        out.extend(quote!(.defines(#def_list)));
+    }
+    let target = variant_tokens(krate, "TargetEnv", cfg.target);
+    out.extend(quote!(.target(#target)));
+    let entry = &cfg.entry;
+    out.extend(quote!(.entry(#entry)));
+    let optim_lv = variant_tokens(krate, "OptimizationLevel", cfg.optim_lv);
+    out.extend(quote!(.optimization(#optim_lv)));
+    let debug = cfg.debug;
+    let auto_bind = cfg.auto_bind;
+    let y_flip = cfg.y_flip;
+    out.extend(quote!(.debug(#debug).auto_bind(#auto_bind).y_flip(#y_flip)));
 
-            #[cfg(feature = "naga")]
-            "no_y_flip" => cfg.y_flip = false,
+    if let Some((major, minor)) = cfg.hlsl_shader_model {
+        out.extend(quote!(.hlsl_shader_model(#major, #minor)));
+    }
+    let hlsl_16bit_types = cfg.hlsl_16bit_types;
+    let hlsl_offsets = cfg.hlsl_offsets;
+    let hlsl_io_mapping = cfg.hlsl_io_mapping;
+    let hlsl_functionality1 = cfg.hlsl_functionality1;
+    out.extend(quote! {
+        .hlsl_16bit_types(#hlsl_16bit_types)
+        .hlsl_offsets(#hlsl_offsets)
+        .hlsl_io_mapping(#hlsl_io_mapping)
+        .hlsl_functionality1(#hlsl_functionality1)
+    });
+    for (stage, reg, set, binding) in cfg.hlsl_explicit_bindings.iter() {
+        let stage = optional_stage_tokens(krate, *stage);
+        out.extend(quote!(.hlsl_explicit_binding(#stage, #reg, #set, #binding)));
+    }
+    for (stage, res_kind, base) in cfg.binding_bases.iter() {
+        let stage = optional_stage_tokens(krate, *stage);
+        let res_kind = variant_tokens(krate, "ResourceKind", *res_kind);
+        out.extend(quote!(.binding_base(#stage, #res_kind, #base)));
+    }
 
-            _ => return Err(Error::new(k.span(), "unsupported compilation parameter")),
-        }
+    if let Some(version) = cfg.glsl_version {
+        out.extend(quote!(.glsl_version(#version)));
+    }
+    if let Some(profile) = cfg.glsl_profile {
+        let profile = variant_tokens(krate, "GlslProfile", profile);
+        out.extend(quote!(.glsl_profile(#profile)));
+    }
+    if let Some(limits_file) = &cfg.limits_file {
+        out.extend(quote!(.limits_file(#limits_file)));
+    }
+    for (name, value) in cfg.limits.iter() {
+        out.extend(quote!(.limit(#name, #value)));
+    }
+    if let Some((major, minor)) = cfg.spirv_version {
+        out.extend(quote!(.spirv_version(#major, #minor)));
     }
-    Ok(cfg)
+    if let Some(generator) = cfg.generator {
+        out.extend(quote!(.generator(#generator)));
+    }
+    if let Some(bound) = cfg.bound {
+        out.extend(quote!(.bound(#bound)));
+    }
+    Ok(out)
+}
+
+/// Where the shader source comes from.
+enum ShaderSource<'a> {
+    /// An expression of the source text.
+    Text(&'a Expr),
+    /// An expression of the path to the shader file read at runtime.
+    File(&'a Expr),
 }
 
 fn generate_compile_code(
-    src: &Expr,
+    src: ShaderSource,
     cfg: &ShaderCompilationConfig,
 ) -> Result<proc_macro::TokenStream, String> {
     use quote::quote;
-    let mut is_valid = false;
-    // This defualt error should not be visible to the users.
-    let mut out = quote!(Err(String::default()));
-    if let Ok(generated_code) = backends::naga::generate_compile_code(Ident::new("src", Span::call_site()), cfg) {
-        out.extend(quote!(.or_else(#generated_code)));
-        is_valid = true;
-    }
-    if let Ok(generated_code) = backends::shaderc::generate_compile_code(Ident::new("src", Span::call_site()), cfg) {
-        out.extend(quote!(.or_else(#generated_code)));
-        is_valid = true;
-    }
-    if !is_valid {
-        return Err("cannot find a proper shader compiler backend".to_owned());
-    }
+    let krate = crate_path(cfg);
+    let opts = generate_options_code(cfg, &krate)?;
+    let (src, compile) = match (src, &cfg.cache) {
+        (ShaderSource::Text(src), Some(cache)) => (
+            quote!(let src: &str = #src.as_ref();),
+            quote!((#cache).compile(&compiler, src, opts)),
+        ),
+        (ShaderSource::Text(src), None) => (
+            quote!(let src: &str = #src.as_ref();),
+            quote!(compiler.compile(src, opts)),
+        ),
+        (ShaderSource::File(path), Some(cache)) => (
+            quote!(let path = #path;),
+            quote!((#cache).compile_file(&compiler, &path, opts)),
+        ),
+        (ShaderSource::File(path), None) => (
+            quote!(let path = #path;),
+            quote!(compiler.compile_file(&path, opts)),
+        ),
+    };
+    // Options given in literals are built only once. The others are evaluated
+    // on every invocation.
+    let opts = if !has_runtime_options(cfg) {
+        quote! {
+            static OPTS: ::std::sync::OnceLock<#krate::CompileOptions> =
+                ::std::sync::OnceLock::new();
+            let opts = OPTS.get_or_init(|| #opts);
+        }
+    } else {
+        quote!(let opts = &#opts;)
+    };
     let out = quote!({
-        let src: &str = #src.as_ref();
-        let feedback = #out;
-        feedback.map(|x| x.spv)
+        #src
+        #opts
+        #krate::Compiler::shared()
+            .and_then(|compiler| #compile)
     });
     Ok(out.into())
 }
@@ -339,16 +498,37 @@ impl Parse for JitSpirv {
         let src = input.parse::<Expr>()?;
 
         let cfg = parse_compile_cfg(&mut input)?;
-        let tokens = generate_compile_code(&src, &cfg)
+        let tokens = generate_compile_code(ShaderSource::Text(&src), &cfg)
             .map_err(|e| ParseError::new(input.span(), e))?;
         Ok(JitSpirv(tokens))
     }
 }
 
-/// Generate shader compilation code to translate GLSL/HLSL/WGSL to SPIR-V
-/// binary word sequence (`Vec<u32>`).
+struct JitIncludeSpirv(TokenStream);
+impl Parse for JitIncludeSpirv {
+    fn parse(mut input: ParseStream) -> ParseResult<Self> {
+        let path = input.parse::<Expr>()?;
+
+        let cfg = parse_compile_cfg(&mut input)?;
+        let tokens = generate_compile_code(ShaderSource::File(&path), &cfg)
+            .map_err(|e| ParseError::new(input.span(), e))?;
+        Ok(JitIncludeSpirv(tokens))
+    }
+}
+
+/// Generate shader compilation code to translate GLSL/HLSL/WGSL to SPIR-V,
+/// giving a `Result<jit_spirv::CompilationFeedback, String>`.
 #[proc_macro]
 pub fn jit_spirv(tokens: TokenStream) -> TokenStream {
     let JitSpirv(tokens) = parse_macro_input!(tokens as JitSpirv);
     tokens
 }
+
+/// Generate shader compilation code to read a GLSL/HLSL/WGSL shader file at
+/// runtime and translate it to SPIR-V, giving a
+/// `Result<jit_spirv::CompilationFeedback, String>`.
+#[proc_macro]
+pub fn jit_include_spirv(tokens: TokenStream) -> TokenStream {
+    let JitIncludeSpirv(tokens) = parse_macro_input!(tokens as JitIncludeSpirv);
+    tokens
+}
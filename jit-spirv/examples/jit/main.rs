@@ -20,14 +20,18 @@ fn main() {
         }
     "#;
 
+    let hack_scale_constant_id = 233;
     let frag = jit_spirv!(
         glsl_source,
         frag,
         auto_bind,
-        D HACK_SCALE_CONSTANT_ID="233",
+        D HACK_SCALE_CONSTANT_ID=hack_scale_constant_id,
     ).unwrap();
+    for warning in frag.warnings.iter() {
+        println!("warning: {}", warning);
+    }
 
-    let entry_points = ReflectConfig::new().spv(frag).reflect().unwrap();
+    let entry_points = ReflectConfig::new().spv(frag.spv).reflect().unwrap();
     let entry = entry_points.first().unwrap();
 
     println!("{:#?}", entry);
@@ -0,0 +1,376 @@
+//! Compilation cache in memory and on disk.
+use std::collections::HashMap;
+use std::hash::{Hash, Hasher};
+use std::io::{Read, Write};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Mutex, PoisonError};
+use std::time::{Duration, Instant};
+use crate::include::{IncludeRecord, IncludeType, StableHasher};
+use crate::{CompilationFeedback, Compiler, CompileOptions, ShaderStage};
+
+// Bump this whenever the layout of cache files changes.
+const CACHE_FORMAT_VERSION: u32 = 4;
+// Execution model recorded for modules without a known stage.
+const NO_STAGE: u32 = u32::MAX;
+const CACHE_FILE_MAGIC: &[u8; 4] = b"JSPV";
+
+fn hash_bytes(bytes: &[u8]) -> u64 {
+    let mut hasher = StableHasher::new();
+    hasher.write(bytes);
+    hasher.finish()
+}
+
+/// The include resolver, if any, is not part of the key; the includes are
+/// validated by resolving them again instead.
+fn cache_key(src: &str, opts: &CompileOptions) -> u64 {
+    let mut hasher = StableHasher::new();
+    CACHE_FORMAT_VERSION.hash(&mut hasher);
+    env!("CARGO_PKG_VERSION").hash(&mut hasher);
+    src.hash(&mut hasher);
+    opts.hash(&mut hasher);
+    hasher.finish()
+}
+
+#[derive(Clone)]
+struct CacheEntry {
+    spv: Vec<u32>,
+    // Dependencies but the source itself, which is covered by the cache key.
+    dep_paths: Vec<String>,
+    // Dependencies read from the filesystem rather than included, e.g.,
+    // resource limit files, and the hashes of their contents at compilation.
+    file_deps: Vec<(String, u64)>,
+    includes: Vec<IncludeRecord>,
+    warnings: Vec<String>,
+    entry: String,
+    stage: Option<ShaderStage>,
+}
+impl CacheEntry {
+    fn new(opts: &CompileOptions, feedback: &CompilationFeedback) -> Option<CacheEntry> {
+        let dep_paths = feedback.dep_paths.iter()
+            .filter(|path| Some(path.as_str()) != opts.source_path())
+            .cloned()
+            .collect::<Vec<_>>();
+        let file_deps = dep_paths.iter()
+            .filter(|path| !feedback.includes.iter().any(|x| &x.resolved_name == *path))
+            .map(|path| {
+                let bytes = std::fs::read(path).ok()?;
+                Some((path.clone(), hash_bytes(&bytes)))
+            })
+            .collect::<Option<Vec<_>>>()?;
+        let entry = CacheEntry {
+            spv: feedback.spv.clone(),
+            dep_paths,
+            file_deps,
+            includes: feedback.includes.clone(),
+            warnings: feedback.warnings.clone(),
+            entry: feedback.entry.clone(),
+            stage: feedback.stage,
+        };
+        Some(entry)
+    }
+    /// The entry is outdated if any of the includes is resolved to another
+    /// source or another content with `opts`, or any other dependency file
+    /// has changed.
+    fn is_up_to_date(&self, opts: &CompileOptions) -> bool {
+        let are_files_up_to_date = self.file_deps.iter()
+            .all(|(path, hash)| match std::fs::read(path) {
+                Ok(bytes) => hash_bytes(&bytes) == *hash,
+                Err(_) => false,
+            });
+        are_files_up_to_date && self.includes.iter().all(|x| x.is_up_to_date(opts))
+    }
+    fn to_feedback(&self, opts: &CompileOptions, compile_time: Duration) -> CompilationFeedback {
+        let dep_paths = opts.source_path().into_iter()
+            .map(ToOwned::to_owned)
+            .chain(self.dep_paths.iter().cloned())
+            .collect();
+        CompilationFeedback {
+            spv: self.spv.clone(),
+            dep_paths,
+            includes: self.includes.clone(),
+            warnings: self.warnings.clone(),
+            entry: self.entry.clone(),
+            stage: self.stage,
+            compile_time,
+        }
+    }
+
+    fn write_to<W: Write>(&self, w: &mut W) -> std::io::Result<()> {
+        fn write_u32<W: Write>(w: &mut W, x: u32) -> std::io::Result<()> {
+            w.write_all(&x.to_le_bytes())
+        }
+        fn write_str<W: Write>(w: &mut W, x: &str) -> std::io::Result<()> {
+            write_u32(w, x.len() as u32)?;
+            w.write_all(x.as_bytes())
+        }
+        w.write_all(CACHE_FILE_MAGIC)?;
+        write_u32(w, CACHE_FORMAT_VERSION)?;
+        write_str(w, env!("CARGO_PKG_VERSION"))?;
+        write_u32(w, self.dep_paths.len() as u32)?;
+        for path in self.dep_paths.iter() {
+            write_str(w, path)?;
+        }
+        write_u32(w, self.file_deps.len() as u32)?;
+        for (path, hash) in self.file_deps.iter() {
+            write_str(w, path)?;
+            w.write_all(&hash.to_le_bytes())?;
+        }
+        write_u32(w, self.includes.len() as u32)?;
+        for include in self.includes.iter() {
+            write_str(w, &include.includer)?;
+            write_str(w, &include.name)?;
+            write_u32(w, include_type_code(include.ty))?;
+            write_str(w, &include.resolved_name)?;
+            w.write_all(&include.content_hash.to_le_bytes())?;
+        }
+        write_u32(w, self.warnings.len() as u32)?;
+        for warning in self.warnings.iter() {
+            write_str(w, warning)?;
+        }
+        write_str(w, &self.entry)?;
+        write_u32(w, self.stage.map_or(NO_STAGE, ShaderStage::execution_model))?;
+        write_u32(w, self.spv.len() as u32)?;
+        for word in self.spv.iter() {
+            write_u32(w, *word)?;
+        }
+        Ok(())
+    }
+    /// Returns `None` if the entry is malformed or written by another version
+    /// of this crate.
+    fn read_from<R: Read>(r: &mut R) -> Option<CacheEntry> {
+        fn read_u32<R: Read>(r: &mut R) -> Option<u32> {
+            let mut buf = [0u8; 4];
+            r.read_exact(&mut buf).ok()?;
+            Some(u32::from_le_bytes(buf))
+        }
+        fn read_u64<R: Read>(r: &mut R) -> Option<u64> {
+            let mut buf = [0u8; 8];
+            r.read_exact(&mut buf).ok()?;
+            Some(u64::from_le_bytes(buf))
+        }
+        fn read_str<R: Read>(r: &mut R) -> Option<String> {
+            let len = read_u32(r)? as u64;
+            let mut buf = Vec::new();
+            r.take(len).read_to_end(&mut buf).ok()?;
+            if buf.len() as u64 != len { return None; }
+            String::from_utf8(buf).ok()
+        }
+        let mut magic = [0u8; 4];
+        r.read_exact(&mut magic).ok()?;
+        if &magic != CACHE_FILE_MAGIC || read_u32(r)? != CACHE_FORMAT_VERSION {
+            return None;
+        }
+        if read_str(r)? != env!("CARGO_PKG_VERSION") {
+            return None;
+        }
+        let ndep = read_u32(r)?;
+        let dep_paths = (0..ndep)
+            .map(|_| read_str(r))
+            .collect::<Option<Vec<_>>>()?;
+        let nfile_dep = read_u32(r)?;
+        let file_deps = (0..nfile_dep)
+            .map(|_| Some((read_str(r)?, read_u64(r)?)))
+            .collect::<Option<Vec<_>>>()?;
+        let ninclude = read_u32(r)?;
+        let includes = (0..ninclude)
+            .map(|_| {
+                let include = IncludeRecord {
+                    includer: read_str(r)?,
+                    name: read_str(r)?,
+                    ty: include_type_from_code(read_u32(r)?)?,
+                    resolved_name: read_str(r)?,
+                    content_hash: read_u64(r)?,
+                };
+                Some(include)
+            })
+            .collect::<Option<Vec<_>>>()?;
+        let nwarning = read_u32(r)?;
+        let warnings = (0..nwarning)
+            .map(|_| read_str(r))
+            .collect::<Option<Vec<_>>>()?;
+        let entry = read_str(r)?;
+        let stage = match read_u32(r)? {
+            NO_STAGE => None,
+            model => Some(ShaderStage::from_execution_model(model)?),
+        };
+        let nword = read_u32(r)?;
+        let spv = (0..nword)
+            .map(|_| read_u32(r))
+            .collect::<Option<Vec<_>>>()?;
+        Some(CacheEntry { spv, dep_paths, file_deps, includes, warnings, entry, stage })
+    }
+}
+
+fn include_type_code(ty: IncludeType) -> u32 {
+    match ty {
+        IncludeType::Relative => 0,
+        IncludeType::Standard => 1,
+    }
+}
+fn include_type_from_code(code: u32) -> Option<IncludeType> {
+    match code {
+        0 => Some(IncludeType::Relative),
+        1 => Some(IncludeType::Standard),
+        _ => None,
+    }
+}
+
+/// In-memory entries evicted in least-recently-used order.
+struct LruEntries {
+    entries: HashMap<u64, (CacheEntry, u64)>,
+    // Incremented on each access; the entry with the smallest stamp is the
+    // least recently used one.
+    tick: u64,
+}
+impl LruEntries {
+    fn get(&mut self, key: u64) -> Option<CacheEntry> {
+        self.tick += 1;
+        let tick = self.tick;
+        self.entries.get_mut(&key).map(|(entry, last_use)| {
+            *last_use = tick;
+            entry.clone()
+        })
+    }
+    fn insert(&mut self, key: u64, entry: CacheEntry, capacity: usize) {
+        self.tick += 1;
+        self.entries.insert(key, (entry, self.tick));
+        while self.entries.len() > capacity {
+            let lru_key = self.entries.iter()
+                .min_by_key(|(_, (_, last_use))| *last_use)
+                .map(|(key, _)| *key);
+            match lru_key {
+                Some(key) => self.entries.remove(&key),
+                None => break,
+            };
+        }
+    }
+}
+
+/// Cache of compiled shaders, keyed by the source text and the compile
+/// options. An entry is reused only if every include is still resolved to the
+/// same source with the same content, with the resolver in the options if any,
+/// and no other dependency file, e.g., the resource limit file, has changed.
+///
+/// The most recently used entries are kept in memory, and if a directory is
+/// given, all entries are also stored on disk so they survive restarts.
+pub struct ShaderCache {
+    capacity: usize,
+    dir: Option<PathBuf>,
+    entries: Mutex<LruEntries>,
+}
+impl ShaderCache {
+    /// Create a cache keeping at most `capacity` entries in memory.
+    pub fn new(capacity: usize) -> Self {
+        let entries = LruEntries {
+            entries: HashMap::new(),
+            tick: 0,
+        };
+        ShaderCache {
+            capacity,
+            dir: None,
+            entries: Mutex::new(entries),
+        }
+    }
+    /// Also store the entries in `dir`. The directory is created on the first
+    /// write.
+    pub fn with_dir<P: Into<PathBuf>>(mut self, dir: P) -> Self {
+        self.dir = Some(dir.into());
+        self
+    }
+
+    fn entry_path(dir: &Path, key: u64) -> PathBuf {
+        dir.join(format!("{:016x}.spvcache", key))
+    }
+    fn load_entry(&self, key: u64) -> Option<CacheEntry> {
+        let path = Self::entry_path(self.dir.as_ref()?, key);
+        let file = std::fs::File::open(path).ok()?;
+        CacheEntry::read_from(&mut std::io::BufReader::new(file))
+    }
+    fn store_entry(&self, key: u64, entry: &CacheEntry) -> std::io::Result<()> {
+        let dir = if let Some(dir) = &self.dir { dir } else { return Ok(()) };
+        std::fs::create_dir_all(dir)?;
+        // Write to a temporary file first so that concurrent readers never see
+        // a partially written entry. The temporary file is unique to each
+        // write, so concurrent writers of the same entry don't race either.
+        static NTMP: AtomicUsize = AtomicUsize::new(0);
+        let path = Self::entry_path(dir, key);
+        let tmp_path = path.with_extension(format!("{}-{}.tmp", std::process::id(),
+            NTMP.fetch_add(1, Ordering::Relaxed)));
+        let mut w = std::io::BufWriter::new(std::fs::File::create(&tmp_path)?);
+        entry.write_to(&mut w)?;
+        w.flush()?;
+        drop(w);
+        std::fs::rename(&tmp_path, &path)
+    }
+
+    /// Look up the compiled shader.
+    pub fn get(&self, src: &str, opts: &CompileOptions) -> Option<CompilationFeedback> {
+        let start = Instant::now();
+        let key = cache_key(src, opts);
+        let entry = self.entries.lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .get(key);
+        if let Some(entry) = entry {
+            if entry.is_up_to_date(opts) {
+                return Some(entry.to_feedback(opts, start.elapsed()));
+            }
+        }
+        let entry = self.load_entry(key)?;
+        if !entry.is_up_to_date(opts) {
+            return None;
+        }
+        let feedback = entry.to_feedback(opts, start.elapsed());
+        self.entries.lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .insert(key, entry, self.capacity);
+        Some(feedback)
+    }
+    /// Put a compiled shader into the cache. Failures to write the on-disk
+    /// store are ignored; the shader is simply compiled again next time.
+    pub fn insert(&self, src: &str, opts: &CompileOptions, feedback: &CompilationFeedback) {
+        let key = cache_key(src, opts);
+        let entry = if let Some(entry) = CacheEntry::new(opts, feedback) {
+            entry
+        } else {
+            // Some dependency cannot be read back, so there is no way to
+            // tell whether the entry is up-to-date.
+            return;
+        };
+        let _ = self.store_entry(key, &entry);
+        self.entries.lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .insert(key, entry, self.capacity);
+    }
+    /// Compile the shader with `compiler` unless it's found in the cache.
+    pub fn compile(
+        &self,
+        compiler: &Compiler,
+        src: &str,
+        opts: &CompileOptions,
+    ) -> Result<CompilationFeedback, String> {
+        if let Some(feedback) = self.get(src, opts) {
+            return Ok(feedback);
+        }
+        let feedback = compiler.compile(src, opts)?;
+        self.insert(src, opts, &feedback);
+        Ok(feedback)
+    }
+    /// Read the shader file at `path` and compile it with `compiler` unless
+    /// it's found in the cache. See [`Compiler::compile_file`].
+    pub fn compile_file<P: AsRef<Path>>(
+        &self,
+        compiler: &Compiler,
+        path: P,
+        opts: &CompileOptions,
+    ) -> Result<CompilationFeedback, String> {
+        let (src, opts) = crate::compiler::read_source_file(path.as_ref(), opts)?;
+        self.compile(compiler, &src, &opts)
+    }
+    /// Remove all entries in memory. Entries on disk are kept.
+    pub fn clear(&self) {
+        self.entries.lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .entries.clear();
+    }
+}
@@ -6,11 +6,12 @@
 //!
 //! ## Source Language
 //!
-//! `jit-spirv` currently support three source languages:
+//! `jit-spirv` currently support four source languages:
 //!
 //! - `glsl`: The shader source is in GLSL (enabled by default);
 //! - `hlsl`: The shader source is in HLSL (enabled by default);
-//! - `wgsl`: The shader source is in WGSL.
+//! - `wgsl`: The shader source is in WGSL;
+//! - `spvasm`: The shader source is in [SPIR-V assembly](https://github.com/KhronosGroup/SPIRV-Tools/blob/main/docs/syntax.md) (enabled by default).
 //!
 //! The experimental WGSL support for WebGPU is available when `wgsl` feature is
 //! enabled, but currently you have to compile with a nightly toolchain. Limited
@@ -52,7 +53,7 @@
 //! - `min_size`: Optimize for the minimal output size;
 //! - `max_perf`: Optimize for the best performance;
 //! - `no_debug`: Strip off all the debug information (don't do this if you want
-//! to reflect the SPIR-V and get variable names).
+//!   to reflect the SPIR-V and get variable names).
 //!
 //! ## Compiler Definition
 //!
@@ -98,10 +99,192 @@
 //! inverts the Y-axis due to the discrepancy in NDC (Normalized Device
 //! Coordinates) between WebGPU and Vulkan. If such correction is undesired, you
 //! can opt out with `no_y_flip`.
-pub mod dep;
-pub use jit_spirv_impl::jit_spirv;
-
-pub struct CompilationFeedback {
-    pub spv: Vec<u32>,
-    pub dep_paths: Vec<String>,
-}
+//!
+//! ## Shader Files
+//!
+//! To compile a shader file, use `jit_include_spirv!` or
+//! `Compiler::compile_file`. The file is read at runtime, recorded in
+//! `CompilationFeedback::dep_paths`, and the source language and stage are
+//! inferred from the file extensions unless you specify them:
+//!
+//! ```ignore
+//! let frag = jit_include_spirv!("shaders/water.frag", auto_bind)?;
+//! let vert = compiler.compile_file("shaders/water.vert.hlsl", &opts)?;
+//! ```
+//!
+//! ## Runtime Compilation
+//!
+//! When the shader options are only known at runtime, e.g., the stage and
+//! definitions are read from a material file, you can use [`Compiler`]
+//! directly. [`CompileOptions`] accepts the same set of options as `jit_spirv!`
+//! in a builder pattern:
+//!
+//! ```ignore
+//! let compiler = Compiler::new()?;
+//! let opts = CompileOptions::new()
+//!     .lang(SourceLanguage::Hlsl)
+//!     .stage(ShaderStage::Fragment)
+//!     .entry("ps_main")
+//!     .define("USE_LIGHTMAP", None)
+//!     .target(TargetEnv::Vulkan1_2)
+//!     .optimization(OptimizationLevel::MaxPerformance);
+//! let spv = compiler.compile(&hlsl_source, &opts)?.spv;
+//! ```
+//!
+//! Both `jit_spirv!` and `Compiler::compile` give a [`CompilationFeedback`].
+//! Besides the SPIR-V words, it tells you which files the shader depends on,
+//! the warnings reported by the compiler, the entry point and stage actually
+//! compiled, and how long the compilation took:
+//!
+//! ```ignore
+//! let feedback = jit_spirv!(glsl_source, frag)?;
+//! for warning in feedback.warnings.iter() {
+//!     log::warn!("{}", warning);
+//! }
+//! println!("compiled `{}` ({:?}) in {:?}", feedback.entry, feedback.stage,
+//!     feedback.compile_time);
+//! ```
+//!
+//! A `Compiler` is safe to share among threads. `Compiler::shared()` gives the
+//! one `jit_spirv!` compiles with, so that compiler instances are not created
+//! over and over again when you compile many shader permutations.
+//!
+//! ## Include Resolution
+//!
+//! By default `#include`s are read from the filesystem. If your shaders live
+//! elsewhere, e.g., in packed asset archives, implement [`IncludeResolver`] and
+//! give it to the compiler. [`MemoryResolver`] serves sources in memory, and
+//! [`LayeredResolver`] stacks resolvers so that, for example, a mod overlay
+//! takes precedence over the base assets:
+//!
+//! ```ignore
+//! let resolver = Arc::new(LayeredResolver::new()
+//!     .layer(MemoryResolver::new().file("common/lighting.glsl", mod_lighting))
+//!     .layer(FsResolver));
+//! let opts = CompileOptions::new().shared_resolver(resolver.clone());
+//! jit_spirv!(glsl_source, frag, resolver=resolver.clone());
+//! ```
+//!
+//! Resolved includes are recorded by their canonical names in
+//! `CompilationFeedback::dep_paths`.
+//!
+//! ## Compilation Cache
+//!
+//! A [`ShaderCache`] keeps compiled shaders keyed by the source text and the
+//! compile options. The most recently used entries are kept in memory, and all
+//! of them are also stored on disk if a cache directory is given, so after the
+//! first run your application skips compilation at startup. An entry is reused
+//! only if every include is still resolved to the same source with the same
+//! content, by the include resolver if any, and entries written by other
+//! versions of this crate are ignored.
+//!
+//! ```ignore
+//! let cache = ShaderCache::new(256).with_dir("target/shader-cache");
+//! let spv = cache.compile(&compiler, &glsl_source, &opts)?.spv;
+//! ```
+//!
+//! `jit_spirv!` compiles with a cache if you give one with `cache=expr`:
+//!
+//! ```ignore
+//! jit_spirv!(glsl_source, frag, cache=&*CACHE);
+//! ```
+//!
+//! ## Hot Reloading
+//!
+//! A [`ShaderWatcher`] compiles a shader file and recompiles it whenever the
+//! file or any file it includes is modified. If the shader fails to recompile,
+//! the last good binary is kept so your application can keep running while you
+//! fix the error:
+//!
+//! ```ignore
+//! let watcher = ShaderWatcher::new("shaders/water.frag", opts,
+//!     Duration::from_millis(500))?;
+//! loop {
+//!     match watcher.try_recv() {
+//!         Some(WatchEvent::Compiled(feedback)) => rebuild_pipeline(&feedback.spv),
+//!         Some(WatchEvent::Failed(e)) => eprintln!("{}", e),
+//!         None => {},
+//!     }
+//!     draw_frame(&watcher.spv());
+//! }
+//! ```
+//!
+//! Use `ShaderWatcher::with_callback` instead if you prefer to handle updates
+//! in a callback. Files are polled in a background thread, which is stopped
+//! when the watcher is dropped.
+//!
+//! ## Background Compilation
+//!
+//! To keep large shaders from stalling your main thread, compile them in the
+//! background with a [`CompilePool`]. Each submission returns a
+//! [`CompileHandle`], which you can `.await` in any async runtime, poll with
+//! `is_done`, or block on with `wait`:
+//!
+//! ```ignore
+//! let pool = CompilePool::new(4, 64)?;
+//! let handle = pool.submit_latest("water.frag", src, opts);
+//! // ...
+//! if handle.is_done() {
+//!     let spv = handle.wait()?.spv;
+//! }
+//! ```
+//!
+//! Submissions with the same key are versions of the same shader, e.g., as it's
+//! being edited. Once a newer version is submitted, the older job is cancelled
+//! and its handle resolves to an error; `is_cancelled` tells you about that.
+//!
+//! ## SPIR-V Assembly
+//!
+//! SPIR-V assembly generated at runtime can be assembled with `spvasm`, or
+//! `SourceLanguage::Spvasm` in the runtime API. The sources are preprocessed
+//! with `#include`, `#define` and `#ifdef` as in `inline-spirv`. The module
+//! header is taken from the header comments as `spirv-dis` prints them, and can
+//! be overridden with `spirv_version`, `generator` and `bound`:
+//!
+//! ```ignore
+//! let opts = CompileOptions::new()
+//!     .lang(SourceLanguage::Spvasm)
+//!     .spirv_version(1, 3)
+//!     .generator(0x000d_000a);
+//! let spv = compiler.compile(&spvasm_source, &opts)?.spv;
+//! let text = disassemble_spirv(&spv)?;
+//! ```
+//!
+//! ## Link Multiple Modules
+//!
+//! Modules compiled separately, e.g., a shader and a library of shared
+//! utility functions with export linkage, can be linked into one with
+//! `link_spirv`. The modules are linked for the target environment in the
+//! options, and only the entry point named by `entry` is kept in the linked
+//! module:
+//!
+//! ```ignore
+//! let opts = CompileOptions::new()
+//!     .target(TargetEnv::Vulkan1_2)
+//!     .entry("main");
+//! let spv = link_spirv(&[&shader_spv, &utils_spv], &opts).unwrap();
+//! ```
+//!
+//! The modules are linked by `spirv-link` from
+//! [SPIRV-Tools](https://github.com/KhronosGroup/SPIRV-Tools), which is looked
+//! up in `PATH` unless you specify its location in the `SPIRV_LINK` environment
+//! variable.
+pub mod cache;
+pub mod pool;
+pub mod watch;
+pub use spirv_compile_core::{compiler, dep, include, link, CompilationFeedback};
+#[cfg(feature = "spvasm")]
+pub use spirv_compile_core::disasm;
+#[cfg(feature = "shaderc")]
+pub use spirv_compile_core::limits;
+pub use jit_spirv_impl::{jit_spirv, jit_include_spirv};
+pub use link::link_spirv;
+#[cfg(feature = "spvasm")]
+pub use disasm::disassemble_spirv;
+pub use cache::ShaderCache;
+pub use watch::{ShaderWatcher, WatchEvent};
+pub use pool::{CompilePool, CompileHandle};
+pub use include::{IncludeResolver, IncludeType, ResolvedInclude, FsResolver,
+    MemoryResolver, LayeredResolver};
+pub use compiler::{Compiler, CompileOptions, SourceLanguage, ShaderStage,
+    TargetEnv, OptimizationLevel, GlslProfile, ResourceKind};
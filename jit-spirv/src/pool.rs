@@ -0,0 +1,246 @@
+//! Background compilation on a worker pool.
+use std::collections::{HashMap, VecDeque};
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::{Arc, Condvar, Mutex, MutexGuard, PoisonError};
+use std::task::{Context, Poll, Waker};
+use std::thread::JoinHandle;
+use crate::{CompilationFeedback, Compiler, CompileOptions};
+
+const CANCELLED_ERROR: &str = "compilation is cancelled by a newer submission";
+
+fn lock<T>(x: &Mutex<T>) -> MutexGuard<'_, T> {
+    x.lock().unwrap_or_else(PoisonError::into_inner)
+}
+
+#[derive(Default)]
+struct JobResult {
+    result: Option<Result<CompilationFeedback, String>>,
+    is_cancelled: bool,
+    waker: Option<Waker>,
+}
+#[derive(Default)]
+struct JobState {
+    result: Mutex<JobResult>,
+    cond: Condvar,
+}
+impl JobState {
+    fn finish(&self, result: Result<CompilationFeedback, String>) {
+        let mut x = lock(&self.result);
+        if x.result.is_some() { return; }
+        x.result = Some(result);
+        if let Some(waker) = x.waker.take() {
+            waker.wake();
+        }
+        self.cond.notify_all();
+    }
+    fn cancel(&self) {
+        lock(&self.result).is_cancelled = true;
+        self.finish(Err(CANCELLED_ERROR.to_owned()));
+    }
+}
+
+struct Job {
+    key: Option<String>,
+    src: String,
+    opts: CompileOptions,
+    state: Arc<JobState>,
+}
+
+struct PoolQueue {
+    jobs: VecDeque<Job>,
+    // The latest submitted job of each keyed shader, either pending or
+    // running.
+    latest: HashMap<String, Arc<JobState>>,
+    is_closed: bool,
+}
+struct PoolShared {
+    queue: Mutex<PoolQueue>,
+    // Notified when a job is pushed or the pool is closed.
+    job_cond: Condvar,
+    // Notified when a job is popped.
+    space_cond: Condvar,
+    capacity: usize,
+}
+
+/// Handle to a submitted compile job. Wait for the result with
+/// [`wait`](Self::wait), or `.await` it in any async runtime.
+pub struct CompileHandle {
+    state: Arc<JobState>,
+}
+impl CompileHandle {
+    /// Block until the job is done.
+    pub fn wait(self) -> Result<CompilationFeedback, String> {
+        let mut x = lock(&self.state.result);
+        loop {
+            if let Some(result) = x.result.take() {
+                return result;
+            }
+            x = self.state.cond.wait(x).unwrap_or_else(PoisonError::into_inner);
+        }
+    }
+    /// Whether the job is done, either finished or cancelled.
+    pub fn is_done(&self) -> bool {
+        lock(&self.state.result).result.is_some()
+    }
+    /// Whether the job is cancelled because a newer version of the same
+    /// shader has been submitted.
+    pub fn is_cancelled(&self) -> bool {
+        lock(&self.state.result).is_cancelled
+    }
+}
+impl Future for CompileHandle {
+    type Output = Result<CompilationFeedback, String>;
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let mut x = lock(&self.state.result);
+        match x.result.take() {
+            Some(result) => Poll::Ready(result),
+            None => {
+                x.waker = Some(cx.waker().clone());
+                Poll::Pending
+            },
+        }
+    }
+}
+
+/// Pool of worker threads compiling shaders in the background. At most
+/// `capacity` jobs can be pending; further submissions block until a worker
+/// takes a job.
+///
+/// Jobs submitted with a key are considered versions of the same shader. When
+/// a newer version is submitted, the stale pending one is cancelled without
+/// being compiled, and the result of a stale running one is discarded.
+///
+/// Dropping the pool waits for the pending jobs to finish.
+pub struct CompilePool {
+    shared: Arc<PoolShared>,
+    workers: Vec<JoinHandle<()>>,
+}
+impl CompilePool {
+    pub fn new(nworker: usize, capacity: usize) -> Result<CompilePool, String> {
+        let compiler = Compiler::shared()?;
+        let shared = Arc::new(PoolShared {
+            queue: Mutex::new(PoolQueue {
+                jobs: VecDeque::new(),
+                latest: HashMap::new(),
+                is_closed: false,
+            }),
+            job_cond: Condvar::new(),
+            space_cond: Condvar::new(),
+            capacity: capacity.max(1),
+        });
+        let workers = (0..nworker.max(1))
+            .map(|i| {
+                let shared = shared.clone();
+                let compiler = compiler.clone();
+                std::thread::Builder::new()
+                    .name(format!("jit-spirv-worker-{}", i))
+                    .spawn(move || Self::work(&shared, &compiler))
+                    .map_err(|e| format!("cannot spawn worker thread: {}", e))
+            })
+            .collect::<Result<Vec<_>, String>>()?;
+        Ok(CompilePool { shared, workers })
+    }
+
+    fn work(shared: &PoolShared, compiler: &Compiler) {
+        loop {
+            let job = {
+                let mut queue = lock(&shared.queue);
+                loop {
+                    if let Some(job) = queue.jobs.pop_front() {
+                        break job;
+                    }
+                    if queue.is_closed { return; }
+                    queue = shared.job_cond.wait(queue)
+                        .unwrap_or_else(PoisonError::into_inner);
+                }
+            };
+            shared.space_cond.notify_one();
+
+            let result = compiler.compile(&job.src, &job.opts);
+            let mut queue = lock(&shared.queue);
+            if let Some(key) = &job.key {
+                let is_latest = matches!(queue.latest.get(key),
+                    Some(x) if Arc::ptr_eq(x, &job.state));
+                if is_latest {
+                    queue.latest.remove(key);
+                } else {
+                    drop(queue);
+                    job.state.cancel();
+                    continue;
+                }
+            }
+            drop(queue);
+            job.state.finish(result);
+        }
+    }
+
+    fn push(&self, key: Option<String>, src: String, opts: CompileOptions) -> CompileHandle {
+        let state = Arc::new(JobState::default());
+        let mut queue = lock(&self.shared.queue);
+        if let Some(key) = &key {
+            if let Some(stale) = queue.latest.insert(key.clone(), state.clone()) {
+                let i = queue.jobs.iter()
+                    .position(|job| Arc::ptr_eq(&job.state, &stale));
+                // A running stale job is cancelled by its worker, and a stale
+                // submission still waiting for space cancels itself.
+                match i {
+                    Some(i) => {
+                        queue.jobs.remove(i);
+                        self.shared.space_cond.notify_one();
+                        stale.cancel();
+                    },
+                    None => self.shared.space_cond.notify_all(),
+                }
+            }
+        }
+        loop {
+            let is_stale = match &key {
+                Some(key) => !matches!(queue.latest.get(key),
+                    Some(x) if Arc::ptr_eq(x, &state)),
+                None => false,
+            };
+            if is_stale {
+                drop(queue);
+                // Pass on the space this submission might have been notified
+                // of.
+                self.shared.space_cond.notify_one();
+                state.cancel();
+                return CompileHandle { state };
+            }
+            if queue.jobs.len() < self.shared.capacity {
+                break;
+            }
+            queue = self.shared.space_cond.wait(queue)
+                .unwrap_or_else(PoisonError::into_inner);
+        }
+        queue.jobs.push_back(Job { key, src, opts, state: state.clone() });
+        drop(queue);
+        self.shared.job_cond.notify_one();
+        CompileHandle { state }
+    }
+
+    /// Submit a compile job.
+    pub fn submit(&self, src: String, opts: CompileOptions) -> CompileHandle {
+        self.push(None, src, opts)
+    }
+    /// Submit a compile job of the shader identified by `key`, cancelling the
+    /// previously submitted job of the same shader if it's not done yet.
+    pub fn submit_latest<K: Into<String>>(
+        &self,
+        key: K,
+        src: String,
+        opts: CompileOptions,
+    ) -> CompileHandle {
+        self.push(Some(key.into()), src, opts)
+    }
+}
+impl Drop for CompilePool {
+    fn drop(&mut self) {
+        lock(&self.shared.queue).is_closed = true;
+        self.shared.job_cond.notify_all();
+        for worker in self.workers.drain(..) {
+            let _ = worker.join();
+        }
+    }
+}
@@ -0,0 +1,223 @@
+//! Shader hot-reloading.
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{channel, Receiver};
+use std::sync::{Arc, Mutex, PoisonError};
+use std::thread::JoinHandle;
+use std::time::{Duration, Instant, SystemTime};
+use crate::{CompilationFeedback, Compiler, CompileOptions};
+
+/// Update delivered by a [`ShaderWatcher`] after a watched file has changed.
+pub enum WatchEvent {
+    /// The shader has been recompiled.
+    Compiled(CompilationFeedback),
+    /// The shader failed to recompile. The last successfully compiled binary
+    /// is kept.
+    Failed(String),
+}
+
+type WatchCallback = Box<dyn FnMut(WatchEvent) + Send>;
+
+struct WatchState {
+    path: PathBuf,
+    opts: CompileOptions,
+    last_good: Arc<Mutex<CompilationFeedback>>,
+    // Watched files and their modification time before they were last read.
+    watched: Vec<(PathBuf, Option<SystemTime>)>,
+}
+impl WatchState {
+    fn mtime(path: &PathBuf) -> Option<SystemTime> {
+        std::fs::metadata(path).and_then(|x| x.modified()).ok()
+    }
+    /// Watch the source file and `paths`. The modification times of the files
+    /// in `snapshot` were taken before the compilation read them; files newly
+    /// discovered by the compilation are compared against the time it started
+    /// instead, so any change saved during the compilation is noticed.
+    fn watch(
+        &mut self,
+        paths: &[String],
+        snapshot: &[(PathBuf, Option<SystemTime>)],
+        started: SystemTime,
+    ) {
+        let mut watched = vec![self.path.clone()];
+        watched.extend(paths.iter().map(PathBuf::from));
+        watched.sort();
+        watched.dedup();
+        self.watched = watched.into_iter()
+            .map(|path| {
+                let mtime = match snapshot.iter().find(|(x, _)| *x == path) {
+                    Some((_, mtime)) => *mtime,
+                    None => Self::mtime(&path).map(|mtime| mtime.min(started)),
+                };
+                (path, mtime)
+            })
+            .collect();
+    }
+    fn is_changed(&self) -> bool {
+        self.watched.iter().any(|(path, mtime)| Self::mtime(path) != *mtime)
+    }
+    fn compile(&mut self) -> Result<CompilationFeedback, String> {
+        // Take the modification times before reading anything, otherwise an
+        // edit saved during the compilation would be taken as already seen.
+        let started = SystemTime::now();
+        let snapshot = std::iter::once(&self.path)
+            .chain(self.watched.iter().map(|(path, _)| path))
+            .map(|path| (path.clone(), Self::mtime(path)))
+            .collect::<Vec<_>>();
+        let res = std::fs::read_to_string(&self.path)
+            .map_err(|e| format!("cannot read from \"{}\": {}", self.path.display(), e))
+            .and_then(|src| Compiler::shared()?.compile(&src, &self.opts));
+        match &res {
+            Ok(feedback) => self.watch(&feedback.dep_paths, &snapshot, started),
+            // Includes are unknown if the compilation failed, so keep
+            // watching the ones of the last good compilation, and wait for
+            // the next change since the snapshot.
+            Err(_) => {
+                for (path, mtime) in self.watched.iter_mut() {
+                    *mtime = snapshot.iter()
+                        .find(|(x, _)| x == path)
+                        .and_then(|(_, mtime)| *mtime);
+                }
+            },
+        }
+        res
+    }
+    fn update(&mut self) -> WatchEvent {
+        match self.compile() {
+            Ok(feedback) => {
+                *self.last_good.lock().unwrap_or_else(PoisonError::into_inner) = feedback.clone();
+                WatchEvent::Compiled(feedback)
+            },
+            Err(e) => WatchEvent::Failed(e),
+        }
+    }
+}
+
+/// Compile a shader file and recompile it whenever the file or any of its
+/// includes changes. Files are polled in a background thread, which stops when
+/// the watcher is dropped.
+pub struct ShaderWatcher {
+    last_good: Arc<Mutex<CompilationFeedback>>,
+    events: Option<Receiver<WatchEvent>>,
+    stop: Arc<AtomicBool>,
+    thread: Option<JoinHandle<()>>,
+}
+impl ShaderWatcher {
+    fn spawn(
+        path: PathBuf,
+        opts: CompileOptions,
+        interval: Duration,
+        mut callback: WatchCallback,
+    ) -> Result<ShaderWatcher, String> {
+        let opts = opts.source_file(&path);
+        let mut state = WatchState {
+            path,
+            opts,
+            last_good: Arc::new(Mutex::new(CompilationFeedback::default())),
+            watched: Vec::new(),
+        };
+        // The initial compilation must succeed, so there is always a good
+        // binary to fall back to.
+        let feedback = state.compile()?;
+        *state.last_good.lock().unwrap_or_else(PoisonError::into_inner) = feedback;
+
+        let last_good = state.last_good.clone();
+        let stop = Arc::new(AtomicBool::new(false));
+        let thread = {
+            let stop = stop.clone();
+            std::thread::Builder::new()
+                .name("jit-spirv-watcher".to_owned())
+                .spawn(move || loop {
+                    // Sleep for the interval, unless unparked by the watcher
+                    // being dropped. Parking can also end spuriously, so the
+                    // deadline is checked again.
+                    let deadline = Instant::now() + interval;
+                    loop {
+                        if stop.load(Ordering::Acquire) {
+                            return;
+                        }
+                        let now = Instant::now();
+                        if now >= deadline {
+                            break;
+                        }
+                        std::thread::park_timeout(deadline - now);
+                    }
+                    if state.is_changed() {
+                        callback(state.update());
+                    }
+                })
+                .map_err(|e| format!("cannot spawn watcher thread: {}", e))?
+        };
+        let out = ShaderWatcher {
+            last_good,
+            events: None,
+            stop,
+            thread: Some(thread),
+        };
+        Ok(out)
+    }
+
+    /// Watch the shader file at `path`, polling for changes every `interval`.
+    /// The source language and the stage are inferred from the file
+    /// extensions unless specified in `opts`.
+    /// Updates are received with [`try_recv`](Self::try_recv) or
+    /// [`recv`](Self::recv). Fails if the shader cannot be compiled at first.
+    pub fn new<P: Into<PathBuf>>(
+        path: P,
+        opts: CompileOptions,
+        interval: Duration,
+    ) -> Result<ShaderWatcher, String> {
+        let (tx, rx) = channel();
+        let callback = Box::new(move |event| {
+            let _ = tx.send(event);
+        });
+        let mut out = Self::spawn(path.into(), opts, interval, callback)?;
+        out.events = Some(rx);
+        Ok(out)
+    }
+    /// Like [`new`](Self::new) but updates are delivered to `callback` in the
+    /// watcher thread.
+    pub fn with_callback<P, F>(
+        path: P,
+        opts: CompileOptions,
+        interval: Duration,
+        callback: F,
+    ) -> Result<ShaderWatcher, String>
+    where
+        P: Into<PathBuf>,
+        F: FnMut(WatchEvent) + Send + 'static,
+    {
+        Self::spawn(path.into(), opts, interval, Box::new(callback))
+    }
+
+    /// The last successfully compiled shader.
+    pub fn feedback(&self) -> CompilationFeedback {
+        self.last_good.lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .clone()
+    }
+    /// Words of the last successfully compiled shader.
+    pub fn spv(&self) -> Vec<u32> {
+        self.feedback().spv
+    }
+    /// Take a pending update without blocking. Always `None` if the watcher
+    /// is created with a callback.
+    pub fn try_recv(&self) -> Option<WatchEvent> {
+        self.events.as_ref()?.try_recv().ok()
+    }
+    /// Block until the next update. Returns `None` if the watcher is created
+    /// with a callback.
+    pub fn recv(&self) -> Option<WatchEvent> {
+        self.events.as_ref()?.recv().ok()
+    }
+}
+impl Drop for ShaderWatcher {
+    fn drop(&mut self) {
+        self.stop.store(true, Ordering::Release);
+        if let Some(thread) = self.thread.take() {
+            // Wake the thread up so it doesn't finish the current interval.
+            thread.thread().unpark();
+            let _ = thread.join();
+        }
+    }
+}
@@ -0,0 +1,157 @@
+use std::path::PathBuf;
+use std::sync::Arc;
+use jit_spirv::include::{IncludeRecord, IncludeType, MemoryResolver, ResolvedInclude};
+use jit_spirv::{CompilationFeedback, CompileOptions, ShaderCache, ShaderStage};
+
+fn test_dir(name: &str) -> PathBuf {
+    let dir = PathBuf::from(env!("CARGO_TARGET_TMPDIR")).join(name);
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir).unwrap();
+    dir
+}
+
+/// Feedback of a compilation that included `incl` from `includer`, so the
+/// tests don't need a compiler.
+fn feedback(
+    spv: &[u32],
+    includer: &str,
+    incl: Option<(&str, &ResolvedInclude)>,
+) -> CompilationFeedback {
+    let includes = incl.into_iter()
+        .map(|(name, incl)| IncludeRecord::new(includer, name, IncludeType::Relative, incl))
+        .collect::<Vec<_>>();
+    CompilationFeedback {
+        spv: spv.to_vec(),
+        dep_paths: includes.iter().map(|x| x.resolved_name.clone()).collect(),
+        includes,
+        entry: "main".to_owned(),
+        stage: Some(ShaderStage::Fragment),
+        ..Default::default()
+    }
+}
+
+fn frag_opts() -> CompileOptions {
+    CompileOptions::new().stage(ShaderStage::Fragment)
+}
+fn memory_opts(content: &str) -> CompileOptions {
+    let resolver = MemoryResolver::new().file("common/light.glsl", content);
+    frag_opts().resolver(resolver)
+}
+fn memory_include(content: &str) -> ResolvedInclude {
+    ResolvedInclude { name: "common/light.glsl".to_owned(), content: content.to_owned() }
+}
+
+#[test]
+fn inserted_shaders_are_found() {
+    let cache = ShaderCache::new(4);
+    let opts = frag_opts();
+    assert!(cache.get("void main() {}", &opts).is_none());
+    cache.insert("void main() {}", &opts, &feedback(&[1, 2, 3], "<inline>", None));
+    let hit = cache.get("void main() {}", &opts).unwrap();
+    assert_eq!(hit.spv, [1, 2, 3]);
+    assert_eq!(hit.entry, "main");
+    assert_eq!(hit.stage, Some(ShaderStage::Fragment));
+    assert!(cache.get("void main() { }", &opts).is_none());
+    assert!(cache.get("void main() {}", &CompileOptions::new().stage(ShaderStage::Vertex)).is_none());
+}
+
+#[test]
+fn changed_included_files_invalidate_entries() {
+    let dir = test_dir("changed_included_files_invalidate_entries");
+    let src_path = dir.join("main.glsl").to_string_lossy().into_owned();
+    let incl_path = dir.join("light.glsl");
+    std::fs::write(&incl_path, "vec3 light;").unwrap();
+    let incl = ResolvedInclude {
+        name: incl_path.to_string_lossy().into_owned(),
+        content: "vec3 light;".to_owned(),
+    };
+
+    let cache = ShaderCache::new(4);
+    let opts = frag_opts().path(src_path.clone());
+    cache.insert("#include \"light.glsl\"", &opts,
+        &feedback(&[1], &src_path, Some(("light.glsl", &incl))));
+    assert!(cache.get("#include \"light.glsl\"", &opts).is_some());
+
+    std::fs::write(&incl_path, "vec4 light;").unwrap();
+    assert!(cache.get("#include \"light.glsl\"", &opts).is_none());
+    std::fs::remove_file(&incl_path).unwrap();
+    assert!(cache.get("#include \"light.glsl\"", &opts).is_none());
+}
+
+#[test]
+fn includes_are_validated_with_the_resolver() {
+    let cache = ShaderCache::new(4);
+    let src = "#include \"common/light.glsl\"";
+    let incl = memory_include("vec3 light;");
+    cache.insert(src, &memory_opts("vec3 light;"),
+        &feedback(&[1], "<inline>", Some(("common/light.glsl", &incl))));
+    // Another resolver instance with the same content hits.
+    assert_eq!(cache.get(src, &memory_opts("vec3 light;")).unwrap().spv, [1]);
+    assert!(cache.get(src, &memory_opts("vec4 light;")).is_none());
+    let empty = frag_opts().resolver(MemoryResolver::new());
+    assert!(cache.get(src, &empty).is_none());
+}
+
+#[test]
+fn least_recently_used_entries_are_evicted() {
+    let cache = ShaderCache::new(2);
+    let opts = frag_opts();
+    cache.insert("a", &opts, &feedback(&[1], "<inline>", None));
+    cache.insert("b", &opts, &feedback(&[2], "<inline>", None));
+    assert!(cache.get("a", &opts).is_some());
+    cache.insert("c", &opts, &feedback(&[3], "<inline>", None));
+    assert!(cache.get("a", &opts).is_some());
+    assert!(cache.get("b", &opts).is_none());
+    assert!(cache.get("c", &opts).is_some());
+}
+
+#[test]
+fn entries_on_disk_survive_restarts() {
+    let dir = test_dir("entries_on_disk_survive_restarts");
+    let src = "#include \"common/light.glsl\"";
+    let incl = memory_include("vec3 light;");
+    let mut fb = feedback(&[0x07230203, 7], "<inline>", Some(("common/light.glsl", &incl)));
+    fb.warnings = vec!["unused variable".to_owned()];
+    ShaderCache::new(4).with_dir(&dir)
+        .insert(src, &memory_opts("vec3 light;"), &fb);
+
+    let cache = ShaderCache::new(4).with_dir(&dir);
+    let hit = cache.get(src, &memory_opts("vec3 light;")).unwrap();
+    assert_eq!(hit.spv, fb.spv);
+    assert_eq!(hit.dep_paths, fb.dep_paths);
+    assert_eq!(hit.includes, fb.includes);
+    assert_eq!(hit.warnings, fb.warnings);
+    assert_eq!(hit.entry, "main");
+    assert_eq!(hit.stage, Some(ShaderStage::Fragment));
+    assert!(cache.get(src, &memory_opts("vec4 light;")).is_none());
+    // Entries evicted from memory are loaded again from disk.
+    let cache = ShaderCache::new(0).with_dir(&dir);
+    assert!(cache.get(src, &memory_opts("vec3 light;")).is_some());
+}
+
+#[test]
+fn concurrent_inserts_do_not_race() {
+    let dir = test_dir("concurrent_inserts_do_not_race");
+    let cache = Arc::new(ShaderCache::new(4).with_dir(&dir));
+    let threads = (0..8)
+        .map(|_| {
+            let cache = cache.clone();
+            std::thread::spawn(move || {
+                let opts = frag_opts();
+                for _ in 0..16 {
+                    cache.insert("void main() {}", &opts, &feedback(&[1, 2], "<inline>", None));
+                }
+            })
+        })
+        .collect::<Vec<_>>();
+    for thread in threads {
+        thread.join().unwrap();
+    }
+    let cache = ShaderCache::new(4).with_dir(&dir);
+    let hit = cache.get("void main() {}", &frag_opts()).unwrap();
+    assert_eq!(hit.spv, [1, 2]);
+    let leftovers = std::fs::read_dir(&dir).unwrap()
+        .filter(|x| x.as_ref().unwrap().path().extension().unwrap() == "tmp")
+        .count();
+    assert_eq!(leftovers, 0);
+}
@@ -0,0 +1,58 @@
+//! Invocations of the macros compiling shaders at runtime. The shaders are
+//! shared with the tests of `inline-spirv` in `tests/shaders` of the workspace.
+use std::path::{Path, PathBuf};
+use jit_spirv::{jit_spirv, jit_include_spirv};
+
+fn shader_dir() -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR")).join("../tests/shaders")
+}
+
+/// Definitions and include directories can be lists only known at runtime,
+/// e.g., the ones of a material loaded from disk.
+#[cfg(feature = "glsl")]
+#[test]
+fn definitions_and_include_dirs_are_given_at_runtime() {
+    let src = r#"
+        #version 450
+        #include <light.glsl>
+        layout(location = 0) out vec4 color;
+        void main() {
+            color = vec4(light(LIGHT_COUNT) * SCALE);
+        }
+    "#;
+    let incl_dirs = vec![shader_dir()];
+    let spvs = [(1, 0.5), (4, 2.0)].iter()
+        .map(|(count, scale)| {
+            let defs = vec![("LIGHT_COUNT", Some(count.to_string())), ("SCALE", Some(scale.to_string()))];
+            let feedback = jit_spirv!(src, glsl, frag, include_dirs=&incl_dirs, defines=defs,
+                entry="main")
+                .unwrap();
+            assert!(feedback.dep_paths.iter().any(|x| x.ends_with("light.glsl")),
+                "{:?}", feedback.dep_paths);
+            feedback.spv
+        })
+        .collect::<Vec<_>>();
+    assert_ne!(spvs[0], spvs[1]);
+
+    // Missing definitions are only found when the shader is compiled.
+    assert!(jit_spirv!(src, glsl, frag, include_dirs=&incl_dirs).is_err());
+}
+
+/// Sources and paths can be built at runtime too.
+#[cfg(feature = "wgsl")]
+#[test]
+fn sources_and_paths_are_given_at_runtime() {
+    let feedback = jit_include_spirv!(shader_dir().join("quad.wgsl"), wgsl, frag,
+        entry="fs_main")
+        .unwrap();
+    assert_eq!(feedback.entry, "fs_main");
+    assert!(feedback.dep_paths.iter().any(|x| x.ends_with("quad.wgsl")));
+
+    let spvs = [1, 64].iter()
+        .map(|size| {
+            let src = format!("@compute @workgroup_size({}) fn main() {{}}", size);
+            jit_spirv!(&src, wgsl, comp).unwrap().spv
+        })
+        .collect::<Vec<_>>();
+    assert_ne!(spvs[0], spvs[1]);
+}
@@ -0,0 +1,161 @@
+#![cfg(feature = "wgsl")]
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::Arc;
+use std::task::{Context, Poll, Wake};
+use std::thread::Thread;
+use jit_spirv::{CompileOptions, CompilePool, SourceLanguage};
+
+const SRC: &str = "@compute @workgroup_size(1) fn main() {}";
+
+fn wgsl() -> CompileOptions {
+    CompileOptions::new().lang(SourceLanguage::Wgsl)
+}
+
+struct ThreadWaker(Thread);
+impl Wake for ThreadWaker {
+    fn wake(self: Arc<Self>) {
+        self.0.unpark();
+    }
+}
+/// Poll `fut` to completion on the current thread, as an async runtime would.
+fn block_on<F: Future>(fut: F) -> F::Output {
+    let mut fut = Box::pin(fut);
+    let waker = Arc::new(ThreadWaker(std::thread::current())).into();
+    let mut cx = Context::from_waker(&waker);
+    loop {
+        match Pin::as_mut(&mut fut).poll(&mut cx) {
+            Poll::Ready(x) => return x,
+            Poll::Pending => std::thread::park(),
+        }
+    }
+}
+
+#[test]
+fn submitted_jobs_are_compiled() {
+    let pool = CompilePool::new(2, 4).unwrap();
+    let handles = (0..8)
+        .map(|_| pool.submit(SRC.to_owned(), wgsl()))
+        .collect::<Vec<_>>();
+    for handle in handles {
+        let feedback = handle.wait().unwrap();
+        assert_eq!(feedback.spv[0], 0x07230203);
+    }
+    let err = pool.submit("fn".to_owned(), wgsl()).wait().unwrap_err();
+    assert!(!err.is_empty());
+}
+
+#[test]
+fn handles_are_futures() {
+    let pool = CompilePool::new(1, 4).unwrap();
+    let handle = pool.submit(SRC.to_owned(), wgsl());
+    let feedback = block_on(handle).unwrap();
+    assert_eq!(feedback.spv[0], 0x07230203);
+    let handle = pool.submit("fn".to_owned(), wgsl());
+    assert!(block_on(async { handle.await.is_err() }));
+}
+
+#[test]
+fn stale_jobs_are_cancelled() {
+    let pool = CompilePool::new(1, 4).unwrap();
+    // The stale job is cancelled whether it's still pending or already
+    // running when the newer one is submitted.
+    let stale = pool.submit_latest("water", SRC.to_owned(), wgsl());
+    let latest = pool.submit_latest("water", SRC.to_owned(), wgsl());
+    let other = pool.submit_latest("sky", SRC.to_owned(), wgsl());
+    assert!(latest.wait().is_ok());
+    assert!(other.wait().is_ok());
+    assert!(stale.is_done());
+    assert!(stale.is_cancelled());
+    assert!(stale.wait().is_err());
+
+    // Done jobs are not affected by newer submissions.
+    let done = pool.submit_latest("water", SRC.to_owned(), wgsl());
+    while !done.is_done() {
+        std::thread::yield_now();
+    }
+    let _newer = pool.submit_latest("water", SRC.to_owned(), wgsl());
+    assert!(!done.is_cancelled());
+    assert!(done.wait().is_ok());
+}
+
+/// Holds the compilations including `gate.glsl` until it's opened.
+#[cfg(feature = "glsl")]
+#[derive(Default)]
+struct Gate {
+    // Whether a compilation has reached the gate, and whether it's open.
+    state: std::sync::Mutex<(bool, bool)>,
+    cond: std::sync::Condvar,
+}
+#[cfg(feature = "glsl")]
+impl Gate {
+    fn wait_reached(&self) {
+        let mut x = self.state.lock().unwrap();
+        while !x.0 {
+            x = self.cond.wait(x).unwrap();
+        }
+    }
+    fn open(&self) {
+        self.state.lock().unwrap().1 = true;
+        self.cond.notify_all();
+    }
+}
+#[cfg(feature = "glsl")]
+struct GateResolver(Arc<Gate>);
+#[cfg(feature = "glsl")]
+impl jit_spirv::IncludeResolver for GateResolver {
+    fn resolve(
+        &self,
+        name: &str,
+        _: jit_spirv::IncludeType,
+        _: &str,
+        _: &[std::path::PathBuf],
+    ) -> Result<jit_spirv::ResolvedInclude, String> {
+        let mut x = self.0.state.lock().unwrap();
+        x.0 = true;
+        self.0.cond.notify_all();
+        while !x.1 {
+            x = self.0.cond.wait(x).unwrap();
+        }
+        Ok(jit_spirv::ResolvedInclude { name: name.to_owned(), content: String::new() })
+    }
+}
+
+#[cfg(feature = "glsl")]
+#[test]
+fn stale_submissions_waiting_for_space_are_cancelled() {
+    use std::time::Duration;
+    use jit_spirv::ShaderStage;
+    const COMP: &str = "#version 450\nlayout(local_size_x = 1) in;\nvoid main() {}";
+    let glsl = || CompileOptions::new()
+        .lang(SourceLanguage::Glsl)
+        .stage(ShaderStage::Compute);
+    let gate = Arc::new(Gate::default());
+    let pool = Arc::new(CompilePool::new(1, 1).unwrap());
+
+    // Keep the only worker busy and the queue full.
+    let blocker = pool.submit(COMP.replace("void main", "#include \"gate.glsl\"\nvoid main"),
+        glsl().resolver(GateResolver(gate.clone())));
+    gate.wait_reached();
+    let pending = pool.submit(COMP.to_owned(), glsl());
+
+    let submit = |delay: u64| {
+        let pool = pool.clone();
+        std::thread::spawn(move || {
+            std::thread::sleep(Duration::from_millis(delay));
+            pool.submit_latest("water", COMP.to_owned(), glsl())
+        })
+    };
+    // Both submissions wait for space. The stale one is cancelled as soon as
+    // the newer one is submitted, while the queue is still full.
+    let stale = submit(0);
+    let latest = submit(200);
+    let stale = stale.join().unwrap();
+    assert!(stale.is_cancelled());
+
+    gate.open();
+    assert!(blocker.wait().is_ok());
+    assert!(pending.wait().is_ok());
+    assert!(latest.join().unwrap().wait().is_ok());
+    assert!(stale.wait().is_err());
+}
@@ -0,0 +1,120 @@
+#![cfg(any(feature = "wgsl", feature = "glsl"))]
+use std::path::PathBuf;
+use std::time::{Duration, Instant};
+use jit_spirv::{CompileOptions, ShaderWatcher, WatchEvent};
+
+fn test_dir(name: &str) -> PathBuf {
+    let dir = PathBuf::from(env!("CARGO_TARGET_TMPDIR")).join(name);
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir).unwrap();
+    dir
+}
+
+/// Wait for the next update for at most a few seconds.
+fn next_event(watcher: &ShaderWatcher) -> WatchEvent {
+    let deadline = Instant::now() + Duration::from_secs(10);
+    loop {
+        if let Some(event) = watcher.try_recv() {
+            return event;
+        }
+        assert!(Instant::now() < deadline, "no update from the watcher");
+        std::thread::sleep(Duration::from_millis(10));
+    }
+}
+
+#[cfg(feature = "wgsl")]
+const FS_MAIN: &str = "@fragment fn fs_main() -> @location(0) vec4<f32> { return vec4<f32>(1.0); }";
+#[cfg(feature = "wgsl")]
+const VS_MAIN: &str = "@vertex fn vs_main() -> @builtin(position) vec4<f32> { return vec4<f32>(0.0); }";
+
+#[cfg(feature = "wgsl")]
+#[test]
+fn changed_shaders_are_recompiled() {
+    let dir = test_dir("changed_shaders_are_recompiled");
+    let path = dir.join("shader.wgsl");
+    std::fs::write(&path, FS_MAIN).unwrap();
+    let opts = CompileOptions::new().entry("fs_main");
+    let watcher = ShaderWatcher::new(&path, opts, Duration::from_millis(10)).unwrap();
+    let first = watcher.spv();
+
+    // Make sure the modification time changes even on filesystems with a
+    // coarse timestamp resolution.
+    std::thread::sleep(Duration::from_millis(1100));
+    std::fs::write(&path, format!("{}\n{}", VS_MAIN, FS_MAIN)).unwrap();
+    match next_event(&watcher) {
+        WatchEvent::Compiled(feedback) => {
+            assert_ne!(feedback.spv, first);
+            assert_eq!(watcher.spv(), feedback.spv);
+        },
+        WatchEvent::Failed(e) => panic!("{}", e),
+    }
+
+    // Failures keep the last good binary.
+    std::thread::sleep(Duration::from_millis(1100));
+    std::fs::write(&path, "@fragment fn fs_main(").unwrap();
+    let last_good = watcher.spv();
+    assert!(matches!(next_event(&watcher), WatchEvent::Failed(_)));
+    assert_eq!(watcher.spv(), last_good);
+}
+
+#[cfg(feature = "wgsl")]
+#[test]
+fn dropping_the_watcher_does_not_wait_for_the_interval() {
+    let dir = test_dir("dropping_the_watcher_does_not_wait_for_the_interval");
+    let path = dir.join("shader.wgsl");
+    std::fs::write(&path, FS_MAIN).unwrap();
+    let watcher = ShaderWatcher::new(&path, CompileOptions::new(), Duration::from_secs(3600))
+        .unwrap();
+    let start = Instant::now();
+    drop(watcher);
+    assert!(start.elapsed() < Duration::from_secs(10));
+}
+
+/// Resolves `light.glsl` and rewrites the shader file meanwhile, once, as if
+/// it was saved in the middle of the compilation.
+#[cfg(feature = "glsl")]
+struct EditingResolver {
+    path: PathBuf,
+    is_edited: std::sync::atomic::AtomicBool,
+}
+#[cfg(feature = "glsl")]
+impl jit_spirv::IncludeResolver for EditingResolver {
+    fn resolve(
+        &self,
+        name: &str,
+        _: jit_spirv::IncludeType,
+        _: &str,
+        _: &[PathBuf],
+    ) -> Result<jit_spirv::ResolvedInclude, String> {
+        use std::sync::atomic::Ordering;
+        if !self.is_edited.swap(true, Ordering::SeqCst) {
+            std::thread::sleep(Duration::from_millis(1100));
+            let src = std::fs::read_to_string(&self.path).unwrap();
+            std::fs::write(&self.path, format!("{}\n", src)).unwrap();
+        }
+        let content = "vec4 light() { return vec4(1.0); }".to_owned();
+        Ok(jit_spirv::ResolvedInclude { name: name.to_owned(), content })
+    }
+}
+
+#[cfg(feature = "glsl")]
+#[test]
+fn edits_during_the_compilation_are_recompiled() {
+    let dir = test_dir("edits_during_the_compilation_are_recompiled");
+    let path = dir.join("shader.frag");
+    std::fs::write(&path, r#"
+        #version 450
+        #include "light.glsl"
+        layout(location = 0) out vec4 color;
+        void main() { color = light(); }
+    "#).unwrap();
+    let resolver = EditingResolver {
+        path: path.clone(),
+        is_edited: Default::default(),
+    };
+    let opts = CompileOptions::new().resolver(resolver);
+    let watcher = ShaderWatcher::new(&path, opts, Duration::from_millis(10)).unwrap();
+    // The edit is saved after the source has been read, so the shader is
+    // compiled again without any further change.
+    assert!(matches!(next_event(&watcher), WatchEvent::Compiled(_)));
+}
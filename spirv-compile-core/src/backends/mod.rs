@@ -0,0 +1,8 @@
+#[cfg(feature = "shaderc")]
+pub(crate) mod shaderc;
+#[cfg(feature = "naga")]
+pub(crate) mod naga;
+#[cfg(feature = "spvasm")]
+pub(crate) mod spirq_spvasm;
+#[cfg(feature = "spvasm")]
+mod spvasm_preprocessor;
@@ -0,0 +1,62 @@
+use crate::dep::naga;
+use crate::compiler::{CompileOptions, ShaderStage, SourceLanguage, TargetEnv};
+use crate::CompilationFeedback;
+
+pub(crate) fn compile(
+    validator: &mut naga::valid::Validator,
+    src: &str,
+    opts: &CompileOptions,
+) -> Result<CompilationFeedback, String> {
+    match opts.lang {
+        None | Some(SourceLanguage::Wgsl) => {},
+        _ => return Err("unsupported source language".to_owned()),
+    }
+
+    let lang_version = match (opts.target, opts.spirv_version) {
+        (target, None) => target.spirv_version(),
+        (TargetEnv::Vulkan1_0, Some(version)) |
+        (TargetEnv::Vulkan1_1, Some(version)) |
+        (TargetEnv::Vulkan1_2, Some(version))
+            if matches!(version, (1, 0) | (1, 3) | (1, 5)) => version,
+        (_, Some((1, 0))) => (1, 0),
+        _ => return Err("unsupported target".to_owned()),
+    };
+    // All entry points are written if the stage is not specified.
+    let stage = match opts.stage {
+        None => None,
+        Some(ShaderStage::Vertex) => Some(naga::ShaderStage::Vertex),
+        Some(ShaderStage::Fragment) => Some(naga::ShaderStage::Fragment),
+        Some(ShaderStage::Compute) => Some(naga::ShaderStage::Compute),
+        _ => return Err("unsupported shader kind".to_owned()),
+    };
+    let mut writer_flags = naga::back::spv::WriterFlags::empty();
+    if opts.debug {
+        writer_flags |= naga::back::spv::WriterFlags::DEBUG;
+    }
+    if opts.y_flip {
+        writer_flags |= naga::back::spv::WriterFlags::ADJUST_COORDINATE_SPACE;
+    }
+
+    let spv_opts = naga::back::spv::Options {
+        lang_version: (lang_version.0 as u8, lang_version.1 as u8),
+        flags: writer_flags,
+        ..Default::default()
+    };
+    let pipe_opts = stage.map(|shader_stage| naga::back::spv::PipelineOptions {
+        shader_stage,
+        entry_point: opts.entry.clone(),
+    });
+    let module = naga::front::wgsl::parse_str(src)
+        .map_err(|e| e.emit_to_string(src))?;
+    let info = validator.validate(&module)
+        .map_err(|e| format!("{:?}", e))?;
+    let spv = naga::back::spv::write_vec(&module, &info, &spv_opts, pipe_opts.as_ref())
+        .map_err(|e| format!("{:?}", e))?;
+    let dep_paths = opts.path.iter().cloned().collect();
+    let feedback = CompilationFeedback {
+        spv,
+        dep_paths,
+        ..Default::default()
+    };
+    Ok(feedback)
+}
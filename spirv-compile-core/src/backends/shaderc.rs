@@ -0,0 +1,210 @@
+use std::cell::RefCell;
+use crate::dep::shaderc;
+use crate::include::{FsResolver, IncludeRecord, IncludeResolver, IncludeType};
+use crate::compiler::{CompileOptions, GlslProfile, OptimizationLevel,
+    ResourceKind, ShaderStage, SourceLanguage, TargetEnv};
+use crate::CompilationFeedback;
+
+/// Forced shader kind of a specific stage.
+fn stage_kind(stage: ShaderStage) -> shaderc::ShaderKind {
+    match stage {
+        ShaderStage::Vertex                 => shaderc::ShaderKind::Vertex,
+        ShaderStage::TessellationControl    => shaderc::ShaderKind::TessControl,
+        ShaderStage::TessellationEvaluation => shaderc::ShaderKind::TessEvaluation,
+        ShaderStage::Geometry               => shaderc::ShaderKind::Geometry,
+        ShaderStage::Fragment               => shaderc::ShaderKind::Fragment,
+        ShaderStage::Compute                => shaderc::ShaderKind::Compute,
+        ShaderStage::Mesh                   => shaderc::ShaderKind::Mesh,
+        ShaderStage::Task                   => shaderc::ShaderKind::Task,
+        ShaderStage::RayGeneration          => shaderc::ShaderKind::RayGeneration,
+        ShaderStage::Intersection           => shaderc::ShaderKind::Intersection,
+        ShaderStage::AnyHit                 => shaderc::ShaderKind::AnyHit,
+        ShaderStage::ClosestHit             => shaderc::ShaderKind::ClosestHit,
+        ShaderStage::Miss                   => shaderc::ShaderKind::Miss,
+        ShaderStage::Callable               => shaderc::ShaderKind::Callable,
+    }
+}
+
+/// Default shader kind of a stage, used when the source doesn't specify it
+/// with `#pragma shader_stage(...)`.
+fn default_stage_kind(stage: Option<ShaderStage>) -> shaderc::ShaderKind {
+    match stage {
+        None                                      => shaderc::ShaderKind::InferFromSource,
+        Some(ShaderStage::Vertex)                 => shaderc::ShaderKind::DefaultVertex,
+        Some(ShaderStage::TessellationControl)    => shaderc::ShaderKind::DefaultTessControl,
+        Some(ShaderStage::TessellationEvaluation) => shaderc::ShaderKind::DefaultTessEvaluation,
+        Some(ShaderStage::Geometry)               => shaderc::ShaderKind::DefaultGeometry,
+        Some(ShaderStage::Fragment)               => shaderc::ShaderKind::DefaultFragment,
+        Some(ShaderStage::Compute)                => shaderc::ShaderKind::DefaultCompute,
+        Some(ShaderStage::Mesh)                   => shaderc::ShaderKind::DefaultMesh,
+        Some(ShaderStage::Task)                   => shaderc::ShaderKind::DefaultTask,
+        Some(ShaderStage::RayGeneration)          => shaderc::ShaderKind::DefaultRayGeneration,
+        Some(ShaderStage::Intersection)           => shaderc::ShaderKind::DefaultIntersection,
+        Some(ShaderStage::AnyHit)                 => shaderc::ShaderKind::DefaultAnyHit,
+        Some(ShaderStage::ClosestHit)             => shaderc::ShaderKind::DefaultClosestHit,
+        Some(ShaderStage::Miss)                   => shaderc::ShaderKind::DefaultMiss,
+        Some(ShaderStage::Callable)               => shaderc::ShaderKind::DefaultCallable,
+    }
+}
+
+fn resource_kind(res_kind: ResourceKind) -> shaderc::ResourceKind {
+    match res_kind {
+        ResourceKind::Image => shaderc::ResourceKind::Image,
+        ResourceKind::Sampler => shaderc::ResourceKind::Sampler,
+        ResourceKind::Texture => shaderc::ResourceKind::Texture,
+        ResourceKind::Buffer => shaderc::ResourceKind::Buffer,
+        ResourceKind::StorageBuffer => shaderc::ResourceKind::StorageBuffer,
+        ResourceKind::UnorderedAccessView => shaderc::ResourceKind::UnorderedAccessView,
+    }
+}
+
+pub(crate) fn compile(
+    compiler: &shaderc::Compiler,
+    src: &str,
+    opts: &CompileOptions,
+) -> Result<CompilationFeedback, String> {
+    let lang = match opts.lang {
+        None | Some(SourceLanguage::Glsl) => shaderc::SourceLanguage::GLSL,
+        Some(SourceLanguage::Hlsl) => shaderc::SourceLanguage::HLSL,
+        _ => return Err("unsupported source language".to_owned()),
+    };
+    // A forced SPIR-V version selects the Vulkan version supporting it.
+    let spirv_version = opts.spirv_version.unwrap_or(opts.target.spirv_version());
+    let (target_env, vulkan_version) = match (opts.target, spirv_version) {
+        (TargetEnv::OpenGL4_5, (1, 0)) => (shaderc::TargetEnv::OpenGL, shaderc::EnvVersion::OpenGL4_5),
+        (TargetEnv::OpenGL4_5, _) | (TargetEnv::WebGpu, _) => {
+            return Err("unsupported target".to_owned());
+        },
+        (_, (1, 0)) => (shaderc::TargetEnv::Vulkan, shaderc::EnvVersion::Vulkan1_0),
+        (_, (1, 3)) => (shaderc::TargetEnv::Vulkan, shaderc::EnvVersion::Vulkan1_1),
+        (_, (1, 5)) => (shaderc::TargetEnv::Vulkan, shaderc::EnvVersion::Vulkan1_2),
+        _ => return Err("unsupported target".to_owned()),
+    };
+    let optim_lv = match opts.optim_lv {
+        OptimizationLevel::None => shaderc::OptimizationLevel::Zero,
+        OptimizationLevel::MinSize => shaderc::OptimizationLevel::Size,
+        OptimizationLevel::MaxPerformance => shaderc::OptimizationLevel::Performance,
+    };
+
+    // Declared ahead of the options so the include callback can borrow it.
+    let dep_paths = RefCell::new(Vec::new());
+    let includes = RefCell::new(Vec::new());
+    let mut opt = shaderc::CompileOptions::new()
+        .ok_or("cannot create `shaderc::CompileOptions`")?;
+    opt.set_target_env(target_env, vulkan_version as u32);
+    opt.set_source_language(lang);
+    opt.set_auto_bind_uniforms(opts.auto_bind);
+    if let Some(SourceLanguage::Hlsl) = opts.lang {
+        if let Some((major, minor)) = opts.hlsl_shader_model {
+            // shaderc doesn't select a shader model; only the macros telling it
+            // are defined.
+            if !crate::compiler::is_hlsl_shader_model((major, minor)) {
+                return Err(format!("unknown shader model {}.{}", major, minor));
+            }
+            if opts.hlsl_16bit_types && (major, minor) < (6, 2) {
+                return Err("16-bit types require shader model 6.2 or above".to_owned());
+            }
+            opt.add_macro_definition("__SHADER_TARGET_MAJOR", Some(&major.to_string()));
+            opt.add_macro_definition("__SHADER_TARGET_MINOR", Some(&minor.to_string()));
+        }
+        opt.set_hlsl_16bit_types(opts.hlsl_16bit_types);
+        opt.set_hlsl_offsets(opts.hlsl_offsets);
+        opt.set_hlsl_io_mapping(opts.hlsl_io_mapping);
+        opt.set_hlsl_functionality1(opts.hlsl_functionality1);
+        for (stage, reg, set, binding) in opts.hlsl_explicit_bindings.iter() {
+            let set = set.to_string();
+            let binding = binding.to_string();
+            match stage {
+                Some(stage) => opt.set_hlsl_register_set_and_binding_for_stage(
+                    stage_kind(*stage), reg, &set, &binding),
+                None => opt.set_hlsl_register_set_and_binding(reg, &set, &binding),
+            }
+        }
+    }
+    for (stage, res_kind, base) in opts.binding_bases.iter() {
+        let res_kind = resource_kind(*res_kind);
+        match stage {
+            Some(stage) => opt.set_binding_base_for_stage(stage_kind(*stage), res_kind, *base),
+            None => opt.set_binding_base(res_kind, *base),
+        }
+    }
+    if let Some(version) = opts.glsl_version {
+        let profile = match opts.glsl_profile {
+            Some(GlslProfile::Core) => shaderc::GlslProfile::Core,
+            Some(GlslProfile::Compatibility) => shaderc::GlslProfile::Compatibility,
+            Some(GlslProfile::Es) => shaderc::GlslProfile::Es,
+            None if [100, 300, 310, 320].contains(&version) => shaderc::GlslProfile::Es,
+            None if version >= 150 => shaderc::GlslProfile::Core,
+            None => shaderc::GlslProfile::None,
+        };
+        opt.set_forced_version_profile(version, profile);
+    } else if opts.glsl_profile.is_some() {
+        return Err("glsl profile is specified without `glsl_version`".to_owned());
+    }
+    if let Some(limits_file) = &opts.limits_file {
+        let limits_file = limits_file.to_string_lossy().into_owned();
+        for (limit, value) in crate::limits::load_limits(&limits_file)? {
+            opt.set_limit(limit, value);
+        }
+        dep_paths.borrow_mut().push(limits_file);
+    }
+    for (name, value) in opts.limits.iter() {
+        let limit = crate::limits::limit_from_name(name)
+            .ok_or_else(|| format!("unknown limit `{}`", name))?;
+        opt.set_limit(limit, *value);
+    }
+    opt.set_optimization_level(optim_lv);
+
+    let resolver: &dyn IncludeResolver = match &opts.resolver {
+        Some(resolver) => &*resolver.0,
+        None => &FsResolver,
+    };
+    opt.set_include_callback(|name, ty, src_path, _depth| {
+        let ty = match ty {
+            shaderc::IncludeType::Relative => IncludeType::Relative,
+            shaderc::IncludeType::Standard => IncludeType::Standard,
+        };
+        let incl = resolver.resolve(name, ty, src_path, &opts.incl_dirs)?;
+        let mut dep_paths = dep_paths.borrow_mut();
+        if !dep_paths.contains(&incl.name) {
+            dep_paths.push(incl.name.clone());
+        }
+        includes.borrow_mut().push(IncludeRecord::new(src_path, name, ty, &incl));
+        let incl = shaderc::ResolvedInclude {
+            resolved_name: incl.name,
+            content: incl.content,
+        };
+        Ok(incl)
+    });
+    for (k, v) in opts.defs.iter() {
+        opt.add_macro_definition(k, v.as_deref());
+    }
+    if opts.debug {
+        opt.set_generate_debug_info();
+    }
+
+    let path = if let Some(path) = &opts.path {
+        dep_paths.borrow_mut().push(path.to_owned());
+        path.as_str()
+    } else { "<inline>" };
+    let out = compiler
+        .compile_into_spirv(src, default_stage_kind(opts.stage), path, &opts.entry, Some(&opt))
+        .map_err(|e| e.to_string())?;
+    drop(opt);
+    let warnings = if out.get_num_warnings() != 0 {
+        out.get_warning_messages()
+            .lines()
+            .filter(|x| !x.is_empty())
+            .map(ToOwned::to_owned)
+            .collect()
+    } else { Vec::new() };
+    let spv = out.as_binary().into();
+    let feedback = CompilationFeedback {
+        spv,
+        dep_paths: dep_paths.into_inner(),
+        includes: includes.into_inner(),
+        warnings,
+        ..Default::default()
+    };
+    Ok(feedback)
+}
@@ -0,0 +1,166 @@
+use crate::dep::spq_spvasm::{asm::Assembler, dis::Disassembler, SpirvBinary, SpirvHeader};
+use crate::compiler::{CompileOptions, SourceLanguage, TargetEnv};
+use crate::CompilationFeedback;
+use super::spvasm_preprocessor::preprocess;
+
+const SPIRV_VERSION_1_0: u32 = 0x0001_0000;
+const SPIRV_VERSION_1_3: u32 = 0x0001_0300;
+const SPIRV_VERSION_1_5: u32 = 0x0001_0500;
+
+// Khronos' reserved vendor ID with no specific tool. Use
+// `CompileOptions::generator` to mark the assembled module as something else.
+const DEFAULT_GENERATOR: u32 = 0;
+
+// Registered generator tool IDs, as `spirv-dis` prints them in the `Generator`
+// header comment.
+const GENERATOR_TOOLS: &[(u32, &str)] = &[
+    (0, "Khronos"),
+    (1, "LunarG"),
+    (2, "Valve"),
+    (3, "Codeplay"),
+    (4, "NVIDIA"),
+    (5, "ARM"),
+    (6, "Khronos LLVM/SPIR-V Translator"),
+    (7, "Khronos SPIR-V Tools Assembler"),
+    (8, "Khronos Glslang Reference Front End"),
+    (9, "Qualcomm"),
+    (10, "AMD"),
+    (11, "Intel"),
+    (12, "Imagination"),
+    (13, "Google Shaderc over Glslang"),
+    (14, "Google spiregg"),
+    (15, "Google rspirv"),
+    (16, "X-LEGEND Mesa-IR/SPIR-V Translator"),
+    (17, "Khronos SPIR-V Tools Linker"),
+    (18, "Wine VKD3D Shader Compiler"),
+    (19, "Tellusim Clay Shader Compiler"),
+    (20, "W3C WebGPU Group WHLSL Shader Translator"),
+    (21, "Google Clspv"),
+    (22, "Google MLIR SPIR-V Serializer"),
+    (23, "Google Tint Compiler"),
+    (24, "Google ANGLE Shader Compiler"),
+    (25, "Netease Games Messiah Shader Compiler"),
+    (26, "Xenia Xenia Emulator Microcode Translator"),
+    (27, "Embark Studios Rust GPU Compiler Backend"),
+    (28, "gfx-rs community Naga"),
+];
+
+/// Module header fields specified in the header comments of the assembly.
+#[derive(Default)]
+struct HeaderComments {
+    version: Option<u32>,
+    generator: Option<u32>,
+    bound: Option<u32>,
+    schema: Option<u32>,
+}
+
+fn parse_generator(value: &str) -> Option<u32> {
+    // `<tool name>; <misc version>`, where an unregistered tool is printed as
+    // `Unknown(<tool id>)`.
+    let (tool, misc) = match value.rfind(';') {
+        Some(i) => (value[..i].trim(), value[i + 1..].trim()),
+        None => return value.parse::<u32>().ok(),
+    };
+    let tool = if tool.starts_with("Unknown(") && tool.ends_with(')') {
+        tool["Unknown(".len()..tool.len() - 1].parse::<u32>().ok()?
+    } else {
+        GENERATOR_TOOLS.iter()
+            .find_map(|(id, name)| if *name == tool { Some(*id) } else { None })?
+    };
+    let misc = misc.parse::<u32>().ok()?;
+    Some((tool << 16) | (misc & 0xffff))
+}
+
+fn parse_header_comments(src: &str) -> Result<HeaderComments, String> {
+    let mut out = HeaderComments::default();
+    for line in src.lines().map(str::trim) {
+        if line.is_empty() { continue; }
+        let comment = if let Some(x) = line.strip_prefix(';') { x.trim() } else { break };
+        let (key, value) = match comment.find(':') {
+            Some(i) => (comment[..i].trim(), comment[i + 1..].trim()),
+            None => continue,
+        };
+        let err = || format!("invalid `{}` in header comments: {}", key, value);
+        match key {
+            "Version" => {
+                let (major, minor) = value.split_once('.')
+                    .and_then(|(major, minor)| Some((major.parse::<u32>().ok()?, minor.parse::<u32>().ok()?)))
+                    .ok_or_else(err)?;
+                out.version = Some((major << 16) | (minor << 8));
+            },
+            "Generator" => out.generator = Some(parse_generator(value).ok_or_else(err)?),
+            "Bound" => out.bound = Some(value.parse::<u32>().map_err(|_| err())?),
+            "Schema" => out.schema = Some(value.parse::<u32>().map_err(|_| err())?),
+            _ => {},
+        }
+    }
+    Ok(out)
+}
+
+pub(crate) fn compile(
+    src: &str,
+    opts: &CompileOptions,
+) -> Result<CompilationFeedback, String> {
+    if opts.lang != Some(SourceLanguage::Spvasm) {
+        return Err("unsupported source language".to_owned());
+    }
+
+    let comments = parse_header_comments(src)?;
+    let version = match opts.spirv_version {
+        Some((major, minor)) => (major << 16) | (minor << 8),
+        None => comments.version.unwrap_or(match opts.target {
+            TargetEnv::Vulkan1_0 => SPIRV_VERSION_1_0,
+            TargetEnv::Vulkan1_1 => SPIRV_VERSION_1_3,
+            TargetEnv::Vulkan1_2 => SPIRV_VERSION_1_5,
+            TargetEnv::OpenGL4_5 => SPIRV_VERSION_1_0,
+            TargetEnv::WebGpu => SPIRV_VERSION_1_0,
+        }),
+    };
+    let generator = opts.generator
+        .or(comments.generator)
+        .unwrap_or(DEFAULT_GENERATOR);
+    let header = SpirvHeader::new(version, generator);
+
+    let path = opts.path.as_deref();
+    let pp = preprocess(src, path, opts)?;
+    let binary = Assembler::new().assemble(&pp.text, header)
+        .map_err(|e| pp.remap_error(&format!("failed to assemble SPIR-V: {}", e)))?;
+    let mut spv = binary.into_words();
+
+    // The assembler always gives the tightest bound; a looser one is only
+    // applied on request.
+    if let Some(bound) = opts.bound.or(comments.bound) {
+        if bound < spv[3] {
+            return Err(format!("bound {} is smaller than the actual bound {}",
+                bound, spv[3]));
+        }
+        spv[3] = bound;
+    }
+    if let Some(schema) = comments.schema {
+        spv[4] = schema;
+    }
+
+    let dep_paths = path.into_iter()
+        .map(|x| x.to_string())
+        .chain(pp.dep_paths)
+        .collect();
+    let feedback = CompilationFeedback {
+        spv,
+        dep_paths,
+        includes: pp.includes,
+        ..Default::default()
+    };
+    Ok(feedback)
+}
+
+pub(crate) fn disassemble(spv: &[u32]) -> Result<String, String> {
+    let binary = SpirvBinary::from(spv.to_vec());
+    Disassembler::new()
+        .print_header(true)
+        .name_ids(true)
+        .name_type_ids(true)
+        .name_const_ids(true)
+        .indent(true)
+        .disassemble(&binary)
+        .map_err(|e| format!("failed to disassemble SPIR-V: {}", e))
+}
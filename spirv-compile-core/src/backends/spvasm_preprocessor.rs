@@ -0,0 +1,278 @@
+use std::collections::HashMap;
+use crate::compiler::CompileOptions;
+use crate::include::{FsResolver, IncludeRecord, IncludeResolver, IncludeType,
+    ResolvedInclude};
+
+// Deep enough for any sane header hierarchy, and shallow enough to stop
+// recursive inclusion before the stack overflows.
+const MAX_INCLUDE_DEPTH: usize = 64;
+
+/// Source location of a line in the preprocessed text.
+#[derive(Clone)]
+pub(crate) struct SourceLocation {
+    pub(crate) path: String,
+    pub(crate) line: usize,
+}
+
+pub(crate) struct PreprocessedSource {
+    pub(crate) text: String,
+    /// Original location of each line in `text`.
+    pub(crate) line_map: Vec<SourceLocation>,
+    /// Paths to all the included files.
+    pub(crate) dep_paths: Vec<String>,
+    /// All the resolved includes.
+    pub(crate) includes: Vec<IncludeRecord>,
+}
+impl PreprocessedSource {
+    /// Replace `line N` in an assembler error message with the original file
+    /// path and line number.
+    pub(crate) fn remap_error(&self, msg: &str) -> String {
+        let mut out = String::new();
+        let mut rest = msg;
+        while let Some(i) = rest.find("line ") {
+            out.push_str(&rest[..i]);
+            rest = &rest[i + 5..];
+            let ndigit = rest.find(|c: char| !c.is_ascii_digit())
+                .unwrap_or(rest.len());
+            let loc = rest[..ndigit].parse::<usize>().ok()
+                .and_then(|x| self.line_map.get(x.wrapping_sub(1)));
+            if let Some(loc) = loc {
+                out.push_str(&format!("{}:{}", loc.path, loc.line));
+                rest = &rest[ndigit..];
+            } else {
+                out.push_str("line ");
+            }
+        }
+        out.push_str(rest);
+        out
+    }
+}
+
+struct Conditional {
+    // The current branch is taken.
+    active: bool,
+    // The enclosing block is taken.
+    parent_active: bool,
+    // `#else` has been seen.
+    has_else: bool,
+    line: usize,
+}
+
+struct Preprocessor<'a> {
+    opts: &'a CompileOptions,
+    resolver: &'a dyn IncludeResolver,
+    defs: HashMap<String, String>,
+    out: PreprocessedSource,
+}
+impl<'a> Preprocessor<'a> {
+    fn new(opts: &'a CompileOptions) -> Self {
+        let resolver: &dyn IncludeResolver = match &opts.resolver {
+            Some(resolver) => &*resolver.0,
+            None => &FsResolver,
+        };
+        let defs = opts.defs.iter()
+            .map(|(k, v)| (k.clone(), v.clone().unwrap_or_default()))
+            .collect();
+        let out = PreprocessedSource {
+            text: String::new(),
+            line_map: Vec::new(),
+            dep_paths: Vec::new(),
+            includes: Vec::new(),
+        };
+        Preprocessor { opts, resolver, defs, out }
+    }
+
+    /// Relative includes fall back to the include directories if they are not
+    /// found next to the includer. The resolution is recorded in the output.
+    fn resolve_include(
+        &mut self,
+        name: &str,
+        is_relative: bool,
+        cur_path: Option<&str>,
+    ) -> Result<ResolvedInclude, String> {
+        let incl_dirs = &self.opts.incl_dirs;
+        let mut resolved = None;
+        if is_relative {
+            if let Some(cur_path) = cur_path {
+                if let Ok(incl) = self.resolver.resolve(name, IncludeType::Relative, cur_path, incl_dirs) {
+                    resolved = Some((IncludeType::Relative, cur_path, incl));
+                }
+            }
+        }
+        let (ty, includer, incl) = match resolved {
+            Some(x) => x,
+            None => {
+                let includer = cur_path.unwrap_or("<inline>");
+                let incl = self.resolver.resolve(name, IncludeType::Standard, includer, incl_dirs)?;
+                (IncludeType::Standard, includer, incl)
+            },
+        };
+        self.out.includes.push(IncludeRecord::new(includer, name, ty, &incl));
+        Ok(incl)
+    }
+
+    /// Substitute defined identifiers outside of string literals. IDs like
+    /// `%NAME` are left untouched.
+    fn substitute(&self, line: &str) -> String {
+        let mut out = String::with_capacity(line.len());
+        let mut chars = line.char_indices().peekable();
+        let mut in_str = false;
+        let mut prev = ' ';
+        while let Some((i, c)) = chars.next() {
+            if in_str {
+                out.push(c);
+                if c == '\\' {
+                    if let Some((_, c)) = chars.next() { out.push(c); }
+                } else if c == '"' {
+                    in_str = false;
+                }
+            } else if c == '"' {
+                in_str = true;
+                out.push(c);
+            } else if c == ';' {
+                // The rest is a comment.
+                out.push_str(&line[i..]);
+                break;
+            } else if c.is_ascii_alphabetic() || c == '_' {
+                let mut end = i + c.len_utf8();
+                while let Some(&(j, c)) = chars.peek() {
+                    if c.is_ascii_alphanumeric() || c == '_' {
+                        end = j + c.len_utf8();
+                        chars.next();
+                    } else {
+                        break;
+                    }
+                }
+                let ident = &line[i..end];
+                match self.defs.get(ident) {
+                    Some(value) if prev != '%' => out.push_str(value),
+                    _ => out.push_str(ident),
+                }
+                prev = 'a';
+                continue;
+            } else {
+                out.push(c);
+            }
+            prev = c;
+        }
+        out
+    }
+
+    fn process(
+        &mut self,
+        src: &str,
+        path: Option<&str>,
+        depth: usize,
+    ) -> Result<(), String> {
+        let display_path = path.unwrap_or("<inline>");
+        let err = |line: usize, msg: &str| {
+            format!("{}:{}: {}", display_path, line, msg)
+        };
+
+        let mut conds: Vec<Conditional> = Vec::new();
+        for (i, line) in src.lines().enumerate() {
+            let line_no = i + 1;
+            let active = conds.last().map(|x| x.active).unwrap_or(true);
+            let trimmed = line.trim_start();
+            if !trimmed.starts_with('#') {
+                if active {
+                    self.out.text.push_str(&self.substitute(line));
+                    self.out.text.push('\n');
+                    self.out.line_map.push(SourceLocation {
+                        path: display_path.to_owned(),
+                        line: line_no,
+                    });
+                }
+                continue;
+            }
+
+            let directive = trimmed[1..].trim_start();
+            let (name, arg) = match directive.find(char::is_whitespace) {
+                Some(i) => (&directive[..i], directive[i..].trim()),
+                None => (directive, ""),
+            };
+            match name {
+                "ifdef" | "ifndef" => {
+                    if arg.is_empty() {
+                        return Err(err(line_no, &format!("#{} without a macro name", name)));
+                    }
+                    let is_defined = self.defs.contains_key(arg);
+                    conds.push(Conditional {
+                        active: active && (is_defined == (name == "ifdef")),
+                        parent_active: active,
+                        has_else: false,
+                        line: line_no,
+                    });
+                },
+                "else" => {
+                    let cond = conds.last_mut()
+                        .ok_or_else(|| err(line_no, "#else without #ifdef"))?;
+                    if cond.has_else {
+                        return Err(err(line_no, "duplicate #else"));
+                    }
+                    cond.has_else = true;
+                    cond.active = cond.parent_active && !cond.active;
+                },
+                "endif" => {
+                    conds.pop()
+                        .ok_or_else(|| err(line_no, "#endif without #ifdef"))?;
+                },
+                _ if !active => {},
+                "define" => {
+                    let (k, v) = match arg.find(char::is_whitespace) {
+                        Some(i) => (&arg[..i], arg[i..].trim()),
+                        None => (arg, ""),
+                    };
+                    if k.is_empty() {
+                        return Err(err(line_no, "#define without a macro name"));
+                    }
+                    let v = self.substitute(v);
+                    self.defs.insert(k.to_owned(), v);
+                },
+                "undef" => {
+                    self.defs.remove(arg);
+                },
+                "include" => {
+                    let (is_relative, name) = if arg.starts_with('"') && arg.ends_with('"') && arg.len() >= 2 {
+                        (true, &arg[1..arg.len() - 1])
+                    } else if arg.starts_with('<') && arg.ends_with('>') {
+                        (false, &arg[1..arg.len() - 1])
+                    } else {
+                        return Err(err(line_no, "#include expects \"FILENAME\" or <FILENAME>"));
+                    };
+                    if is_relative && path.is_none() && self.opts.incl_dirs.is_empty() && self.opts.resolver.is_none() {
+                        return Err(err(line_no, "the shader source is not living in a filesystem, but attempts to include a relative path"));
+                    }
+                    if depth >= MAX_INCLUDE_DEPTH {
+                        return Err(err(line_no, "#include nested too deeply"));
+                    }
+                    let incl = self.resolve_include(name, is_relative, path)
+                        .map_err(|e| err(line_no, &e))?;
+                    if !self.out.dep_paths.contains(&incl.name) {
+                        self.out.dep_paths.push(incl.name.clone());
+                    }
+                    self.process(&incl.content, Some(&incl.name), depth + 1)?;
+                },
+                "error" => return Err(err(line_no, &format!("#error {}", arg))),
+                _ => return Err(err(line_no, &format!("unknown directive #{}", name))),
+            }
+        }
+        if let Some(cond) = conds.last() {
+            return Err(err(cond.line, "unterminated #ifdef"));
+        }
+        Ok(())
+    }
+}
+
+/// Run a C-style preprocessor on SPIR-V assembly. `#include`, `#define`,
+/// `#undef`, `#ifdef`, `#ifndef`, `#else`, `#endif` and `#error` are supported;
+/// macros are object-like only.
+pub(crate) fn preprocess(
+    src: &str,
+    path: Option<&str>,
+    opts: &CompileOptions,
+) -> Result<PreprocessedSource, String> {
+    let mut pp = Preprocessor::new(opts);
+    pp.process(src, path, 0)?;
+    Ok(pp.out)
+}
@@ -0,0 +1,679 @@
+//! Shader compiler.
+//!
+//! `jit_spirv!` generates code on top of this module at runtime, and
+//! `inline_spirv!` lowers its options to [`CompileOptions`] at build time, so
+//! the macros and the runtime API accept the same set of options.
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex, PoisonError};
+use std::time::Instant;
+use crate::CompilationFeedback;
+use crate::include::{IncludeResolver, SharedResolver};
+
+/// Language of the shader source.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum SourceLanguage {
+    Glsl,
+    Hlsl,
+    Wgsl,
+    /// SPIR-V assembly.
+    Spvasm,
+}
+impl SourceLanguage {
+    /// Language named as in the macro options and the file extensions, e.g.,
+    /// `hlsl`.
+    pub fn from_name(name: &str) -> Option<SourceLanguage> {
+        let out = match name {
+            "glsl" => SourceLanguage::Glsl,
+            "hlsl" => SourceLanguage::Hlsl,
+            "wgsl" => SourceLanguage::Wgsl,
+            "spvasm" => SourceLanguage::Spvasm,
+            _ => return None,
+        };
+        Some(out)
+    }
+}
+
+/// Pipeline stage the shader is compiled for.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ShaderStage {
+    Vertex,
+    TessellationControl,
+    TessellationEvaluation,
+    Geometry,
+    Fragment,
+    Compute,
+    // Mesh Pipeline
+    Mesh,
+    Task,
+    // Ray-tracing Pipeline
+    RayGeneration,
+    Intersection,
+    AnyHit,
+    ClosestHit,
+    Miss,
+    Callable,
+}
+impl ShaderStage {
+    /// Stage named as in the macro options and the file extensions, e.g.,
+    /// `frag`.
+    pub fn from_name(name: &str) -> Option<ShaderStage> {
+        let out = match name {
+            "vert" => ShaderStage::Vertex,
+            "tesc" => ShaderStage::TessellationControl,
+            "tese" => ShaderStage::TessellationEvaluation,
+            "geom" => ShaderStage::Geometry,
+            "frag" => ShaderStage::Fragment,
+            "comp" => ShaderStage::Compute,
+            "mesh" => ShaderStage::Mesh,
+            "task" => ShaderStage::Task,
+            "rgen" => ShaderStage::RayGeneration,
+            "rint" => ShaderStage::Intersection,
+            "rahit" => ShaderStage::AnyHit,
+            "rchit" => ShaderStage::ClosestHit,
+            "rmiss" => ShaderStage::Miss,
+            "rcall" => ShaderStage::Callable,
+            _ => return None,
+        };
+        Some(out)
+    }
+    /// SPIR-V `ExecutionModel` of the stage.
+    pub fn execution_model(self) -> u32 {
+        match self {
+            ShaderStage::Vertex => 0,
+            ShaderStage::TessellationControl => 1,
+            ShaderStage::TessellationEvaluation => 2,
+            ShaderStage::Geometry => 3,
+            ShaderStage::Fragment => 4,
+            ShaderStage::Compute => 5,
+            ShaderStage::Task => 5364,
+            ShaderStage::Mesh => 5365,
+            ShaderStage::RayGeneration => 5313,
+            ShaderStage::Intersection => 5314,
+            ShaderStage::AnyHit => 5315,
+            ShaderStage::ClosestHit => 5316,
+            ShaderStage::Miss => 5317,
+            ShaderStage::Callable => 5318,
+        }
+    }
+    /// Stage of a SPIR-V `ExecutionModel`, or `None` if it's not a shader
+    /// stage.
+    pub fn from_execution_model(model: u32) -> Option<ShaderStage> {
+        let out = match model {
+            0 => ShaderStage::Vertex,
+            1 => ShaderStage::TessellationControl,
+            2 => ShaderStage::TessellationEvaluation,
+            3 => ShaderStage::Geometry,
+            4 => ShaderStage::Fragment,
+            5 => ShaderStage::Compute,
+            // NV and EXT mesh shading.
+            5267 | 5364 => ShaderStage::Task,
+            5268 | 5365 => ShaderStage::Mesh,
+            5313 => ShaderStage::RayGeneration,
+            5314 => ShaderStage::Intersection,
+            5315 => ShaderStage::AnyHit,
+            5316 => ShaderStage::ClosestHit,
+            5317 => ShaderStage::Miss,
+            5318 => ShaderStage::Callable,
+            _ => return None,
+        };
+        Some(out)
+    }
+}
+
+/// Whether `shader_model` is an HLSL shader model, i.e., 4.0, 4.1, 5.0, 5.1 or
+/// 6.0 to 6.8.
+#[cfg(any(feature = "shaderc", feature = "syntax"))]
+pub(crate) fn is_hlsl_shader_model((major, minor): (u32, u32)) -> bool {
+    matches!((major, minor), (4, 0..=1) | (5, 0..=1) | (6, 0..=8))
+}
+
+/// Infer the source language and the stage from the extensions of a shader
+/// file name, e.g., `water.frag`, `water.hlsl` or `water.frag.hlsl`.
+fn infer_from_path(path: &Path) -> (Option<SourceLanguage>, Option<ShaderStage>) {
+    let file_name = path.file_name()
+        .map(|x| x.to_string_lossy().to_lowercase())
+        .unwrap_or_default();
+    let mut lang = None;
+    let mut stage = None;
+    // The first segment is the file stem.
+    for ext in file_name.split('.').skip(1) {
+        lang = SourceLanguage::from_name(ext).or(lang);
+        stage = ShaderStage::from_name(ext).or(stage);
+    }
+    // Stage extensions without a language one are GLSL by glslang convention.
+    if lang.is_none() && stage.is_some() {
+        lang = Some(SourceLanguage::Glsl);
+    }
+    (lang, stage)
+}
+
+/// Find the entry points in a SPIR-V module, as pairs of the name and the
+/// execution model.
+fn entry_points(spv: &[u32]) -> Vec<(String, u32)> {
+    const OP_ENTRY_POINT: u32 = 15;
+    let mut out = Vec::new();
+    // Skip the header.
+    let mut words = spv.get(5..).unwrap_or_default();
+    while let Some(word) = words.first() {
+        let (opcode, len) = (word & 0xffff, (word >> 16) as usize);
+        if len == 0 || len > words.len() { break; }
+        if opcode == OP_ENTRY_POINT && len > 3 {
+            let model = words[1];
+            let bytes = words[3..len].iter()
+                .flat_map(|x| x.to_le_bytes())
+                .take_while(|x| *x != 0)
+                .collect::<Vec<_>>();
+            out.push((String::from_utf8_lossy(&bytes).into_owned(), model));
+        }
+        words = &words[len..];
+    }
+    out
+}
+
+/// Target environment and the SPIR-V version it supports.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum TargetEnv {
+    /// Vulkan 1.0, SPIR-V 1.0.
+    Vulkan1_0,
+    /// Vulkan 1.1, SPIR-V 1.3.
+    Vulkan1_1,
+    /// Vulkan 1.2, SPIR-V 1.5.
+    Vulkan1_2,
+    /// OpenGL 4.5 core profile, SPIR-V 1.0.
+    OpenGL4_5,
+    /// WebGPU, SPIR-V 1.0.
+    WebGpu,
+}
+impl TargetEnv {
+    /// Target environment named as in the macro options, e.g., `vulkan1_2`.
+    pub fn from_name(name: &str) -> Option<TargetEnv> {
+        let out = match name {
+            "vulkan" | "vulkan1_0" => TargetEnv::Vulkan1_0,
+            "vulkan1_1" => TargetEnv::Vulkan1_1,
+            "vulkan1_2" => TargetEnv::Vulkan1_2,
+            "opengl" | "opengl4_5" => TargetEnv::OpenGL4_5,
+            "webgpu" => TargetEnv::WebGpu,
+            _ => return None,
+        };
+        Some(out)
+    }
+    /// SPIR-V version supported by the target environment.
+    pub fn spirv_version(&self) -> (u32, u32) {
+        match self {
+            TargetEnv::Vulkan1_0 => (1, 0),
+            TargetEnv::Vulkan1_1 => (1, 3),
+            TargetEnv::Vulkan1_2 => (1, 5),
+            TargetEnv::OpenGL4_5 => (1, 0),
+            TargetEnv::WebGpu => (1, 0),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum OptimizationLevel {
+    None,
+    MinSize,
+    MaxPerformance,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum GlslProfile {
+    Core,
+    Compatibility,
+    Es,
+}
+
+/// Kind of resources that binding bases are assigned to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ResourceKind {
+    Image,
+    Sampler,
+    Texture,
+    Buffer,
+    StorageBuffer,
+    UnorderedAccessView,
+}
+
+/// Options to compile a shader with. Options are set with a builder pattern:
+///
+/// ```ignore
+/// let opts = CompileOptions::new()
+///     .lang(SourceLanguage::Hlsl)
+///     .stage(ShaderStage::Fragment)
+///     .define("USE_LIGHTMAP", None)
+///     .target(TargetEnv::Vulkan1_2);
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct CompileOptions {
+    pub(crate) path: Option<String>,
+    pub(crate) lang: Option<SourceLanguage>,
+    pub(crate) stage: Option<ShaderStage>,
+    pub(crate) incl_dirs: Vec<PathBuf>,
+    pub(crate) resolver: Option<SharedResolver>,
+    pub(crate) defs: Vec<(String, Option<String>)>,
+    pub(crate) target: TargetEnv,
+    pub(crate) entry: String,
+    pub(crate) optim_lv: OptimizationLevel,
+    pub(crate) debug: bool,
+    pub(crate) auto_bind: bool,
+    // HLSL specific.
+    pub(crate) hlsl_shader_model: Option<(u32, u32)>,
+    pub(crate) hlsl_16bit_types: bool,
+    pub(crate) hlsl_offsets: bool,
+    pub(crate) hlsl_io_mapping: bool,
+    pub(crate) hlsl_functionality1: bool,
+    // Applied to all stages if the stage is `None`.
+    pub(crate) hlsl_explicit_bindings: Vec<(Option<ShaderStage>, String, u32, u32)>,
+    pub(crate) binding_bases: Vec<(Option<ShaderStage>, ResourceKind, u32)>,
+    // GLSL specific.
+    pub(crate) glsl_version: Option<u32>,
+    pub(crate) glsl_profile: Option<GlslProfile>,
+    pub(crate) limits: Vec<(String, i32)>,
+    pub(crate) limits_file: Option<PathBuf>,
+    // SPIR-V assembly specific.
+    pub(crate) spirv_version: Option<(u32, u32)>,
+    pub(crate) generator: Option<u32>,
+    pub(crate) bound: Option<u32>,
+    // Backend specific.
+    pub(crate) y_flip: bool,
+}
+impl Default for CompileOptions {
+    fn default() -> Self {
+        CompileOptions {
+            path: None,
+            lang: None,
+            stage: None,
+            incl_dirs: Vec::new(),
+            resolver: None,
+            defs: Vec::new(),
+            target: TargetEnv::Vulkan1_0,
+            entry: "main".to_owned(),
+            optim_lv: OptimizationLevel::None,
+            debug: true,
+            auto_bind: false,
+            hlsl_shader_model: None,
+            hlsl_16bit_types: false,
+            hlsl_offsets: false,
+            hlsl_io_mapping: false,
+            hlsl_functionality1: false,
+            hlsl_explicit_bindings: Vec::new(),
+            binding_bases: Vec::new(),
+            glsl_version: None,
+            glsl_profile: None,
+            limits: Vec::new(),
+            limits_file: None,
+            spirv_version: None,
+            generator: None,
+            bound: None,
+            y_flip: true,
+        }
+    }
+}
+impl CompileOptions {
+    pub fn new() -> Self {
+        Default::default()
+    }
+    /// The path given by [`path`](Self::path) or
+    /// [`source_file`](Self::source_file).
+    pub fn source_path(&self) -> Option<&str> {
+        self.path.as_deref()
+    }
+
+    /// Path to the shader source, used in diagnostics and to resolve relative
+    /// `#include`s. The source is not read from it.
+    pub fn path<P: Into<String>>(mut self, path: P) -> Self {
+        self.path = Some(path.into());
+        self
+    }
+    /// Source language. If not specified, the source is tried as WGSL and
+    /// then as GLSL. SPIR-V assembly is only assembled if it's specified.
+    pub fn lang(mut self, lang: SourceLanguage) -> Self {
+        self.lang = Some(lang);
+        self
+    }
+    /// Shader stage. If not specified, the stage is inferred from the
+    /// `#pragma shader_stage(...)` directive in the source.
+    pub fn stage(mut self, stage: ShaderStage) -> Self {
+        self.stage = Some(stage);
+        self
+    }
+    /// Compile the shader file at `path`. The path is used as with
+    /// [`path`](Self::path), and the source language and the stage are
+    /// inferred from the file extensions unless they are already specified,
+    /// e.g., `.frag` is a GLSL fragment shader and `.vert.hlsl` is an HLSL
+    /// vertex shader.
+    pub fn source_file<P: AsRef<Path>>(mut self, path: P) -> Self {
+        let path = path.as_ref();
+        let (lang, stage) = infer_from_path(path);
+        self.path = Some(path.to_string_lossy().into_owned());
+        self.lang = self.lang.or(lang);
+        self.stage = self.stage.or(stage);
+        self
+    }
+    pub fn include_dir<P: Into<PathBuf>>(mut self, dir: P) -> Self {
+        self.incl_dirs.push(dir.into());
+        self
+    }
+    /// Resolve `#include`s with `resolver` rather than from the filesystem.
+    pub fn resolver<R: IncludeResolver + 'static>(self, resolver: R) -> Self {
+        self.shared_resolver(Arc::new(resolver))
+    }
+    pub fn shared_resolver(mut self, resolver: Arc<dyn IncludeResolver>) -> Self {
+        self.resolver = Some(SharedResolver(resolver));
+        self
+    }
+    pub fn include_dirs<I>(mut self, dirs: I) -> Self
+    where
+        I: IntoIterator,
+        I::Item: Into<PathBuf>,
+    {
+        self.incl_dirs.extend(dirs.into_iter().map(Into::into));
+        self
+    }
+    pub fn define(mut self, name: &str, value: Option<&str>) -> Self {
+        self.defs.push((name.to_owned(), value.map(ToOwned::to_owned)));
+        self
+    }
+    /// Define a list of macros. Values are converted with `ToString`.
+    pub fn defines<I, K, V>(mut self, defs: I) -> Self
+    where
+        I: IntoIterator<Item = (K, Option<V>)>,
+        K: AsRef<str>,
+        V: ToString,
+    {
+        let defs = defs.into_iter()
+            .map(|(k, v)| (k.as_ref().to_owned(), v.map(|v| v.to_string())));
+        self.defs.extend(defs);
+        self
+    }
+    pub fn target(mut self, target: TargetEnv) -> Self {
+        self.target = target;
+        self
+    }
+    pub fn entry<S: Into<String>>(mut self, entry: S) -> Self {
+        self.entry = entry.into();
+        self
+    }
+    pub fn optimization(mut self, optim_lv: OptimizationLevel) -> Self {
+        self.optim_lv = optim_lv;
+        self
+    }
+    pub fn debug(mut self, debug: bool) -> Self {
+        self.debug = debug;
+        self
+    }
+    pub fn auto_bind(mut self, auto_bind: bool) -> Self {
+        self.auto_bind = auto_bind;
+        self
+    }
+    /// Flip the Y-axis for WebGPU. Only effective in the `naga` backend and
+    /// enabled by default.
+    pub fn y_flip(mut self, y_flip: bool) -> Self {
+        self.y_flip = y_flip;
+        self
+    }
+
+    /// Define the `__SHADER_TARGET_MAJOR` and `__SHADER_TARGET_MINOR` macros
+    /// of the shader model. It doesn't change the code generation. Shader
+    /// models that don't exist are rejected in compilation.
+    pub fn hlsl_shader_model(mut self, major: u32, minor: u32) -> Self {
+        self.hlsl_shader_model = Some((major, minor));
+        self
+    }
+    pub fn hlsl_16bit_types(mut self, enable: bool) -> Self {
+        self.hlsl_16bit_types = enable;
+        self
+    }
+    pub fn hlsl_offsets(mut self, enable: bool) -> Self {
+        self.hlsl_offsets = enable;
+        self
+    }
+    pub fn hlsl_io_mapping(mut self, enable: bool) -> Self {
+        self.hlsl_io_mapping = enable;
+        self
+    }
+    pub fn hlsl_functionality1(mut self, enable: bool) -> Self {
+        self.hlsl_functionality1 = enable;
+        self
+    }
+    /// Map an HLSL register to a descriptor set and binding, for a specific
+    /// stage or all stages if `stage` is `None`.
+    pub fn hlsl_explicit_binding(
+        mut self,
+        stage: Option<ShaderStage>,
+        reg: &str,
+        set: u32,
+        binding: u32,
+    ) -> Self {
+        self.hlsl_explicit_bindings.push((stage, reg.to_owned(), set, binding));
+        self
+    }
+    /// Set the binding base of a kind of resources, for a specific stage or
+    /// all stages if `stage` is `None`.
+    pub fn binding_base(
+        mut self,
+        stage: Option<ShaderStage>,
+        res_kind: ResourceKind,
+        base: u32,
+    ) -> Self {
+        self.binding_bases.push((stage, res_kind, base));
+        self
+    }
+
+    pub fn glsl_version(mut self, version: u32) -> Self {
+        self.glsl_version = Some(version);
+        self
+    }
+    pub fn glsl_profile(mut self, profile: GlslProfile) -> Self {
+        self.glsl_profile = Some(profile);
+        self
+    }
+    /// Set a resource limit by its name in glslang resource limit files, e.g.,
+    /// `MaxComputeWorkGroupSizeX`.
+    pub fn limit(mut self, name: &str, value: i32) -> Self {
+        self.limits.push((name.to_owned(), value));
+        self
+    }
+    /// Load resource limits from a glslang resource limit file. Limits set by
+    /// [`limit`](Self::limit) take precedence.
+    pub fn limits_file<P: Into<PathBuf>>(mut self, path: P) -> Self {
+        self.limits_file = Some(path.into());
+        self
+    }
+
+    /// SPIR-V version to generate instead of the one supported by the target
+    /// environment. For GLSL, HLSL and WGSL sources, it selects the Vulkan
+    /// version supporting it, so only 1.0, 1.3 and 1.5 are accepted for Vulkan
+    /// and 1.0 for the other targets. For SPIR-V assembly, it overrides the
+    /// `; Version:` header comment, and any version is accepted.
+    pub fn spirv_version(mut self, major: u32, minor: u32) -> Self {
+        self.spirv_version = Some((major, minor));
+        self
+    }
+    /// Generator magic number of assembled SPIR-V assembly. Overrides the
+    /// `; Generator:` header comment.
+    pub fn generator(mut self, generator: u32) -> Self {
+        self.generator = Some(generator);
+        self
+    }
+    /// ID bound of assembled SPIR-V assembly. Overrides the `; Bound:` header
+    /// comment. It must not be smaller than what the assembly actually uses.
+    pub fn bound(mut self, bound: u32) -> Self {
+        self.bound = Some(bound);
+        self
+    }
+}
+
+/// Read the source at `path` and complete the options with the file path.
+pub fn read_source_file(
+    path: &Path,
+    opts: &CompileOptions,
+) -> Result<(String, CompileOptions), String> {
+    let src = std::fs::read_to_string(path)
+        .map_err(|e| format!("cannot read from \"{}\": {}", path.display(), e))?;
+    Ok((src, opts.clone().source_file(path)))
+}
+
+/// Take the output of a backend, or record the failure if the backend
+/// attempted to compile the source.
+#[cfg_attr(not(any(feature = "shaderc", feature = "naga", feature = "spvasm")),
+    allow(dead_code))]
+fn accept_backend(
+    name: &'static str,
+    out: Result<CompilationFeedback, String>,
+    errors: &mut Vec<(&'static str, String)>,
+) -> Option<CompilationFeedback> {
+    match out {
+        Ok(x) => Some(x),
+        Err(e) => {
+            if e != "unsupported source language" {
+                errors.push((name, e));
+            }
+            None
+        },
+    }
+}
+/// Report the failures of all the backends that attempted to compile the
+/// source. A single failure is reported as is.
+fn aggregate_errors(opts: &CompileOptions, mut errors: Vec<(&'static str, String)>) -> String {
+    match errors.len() {
+        0 => match opts.lang {
+            Some(lang) => format!("no compiler backend is enabled for {:?} sources", lang),
+            None => "no compiler backend is enabled".to_owned(),
+        },
+        1 => errors.pop().unwrap().1,
+        _ => {
+            let mut out = "no compiler backend can compile the source:".to_owned();
+            for (name, e) in errors {
+                out.push_str(&format!("\n{}: {}", name, e.trim_end().replace('\n', "\n  ")));
+            }
+            out
+        },
+    }
+}
+
+/// Shader compiler dispatching compilation to the enabled backends. A
+/// `Compiler` can be shared among threads, and it's cheaper to reuse one than
+/// to create one per compilation.
+pub struct Compiler {
+    #[cfg(feature = "shaderc")]
+    shaderc: crate::dep::shaderc::Compiler,
+    // Idle validators; one is taken out for each compilation so that `naga`
+    // compilations don't block each other.
+    #[cfg(feature = "naga")]
+    validators: Mutex<Vec<crate::dep::naga::valid::Validator>>,
+}
+impl Compiler {
+    pub fn new() -> Result<Compiler, String> {
+        let out = Compiler {
+            #[cfg(feature = "shaderc")]
+            shaderc: crate::dep::shaderc::Compiler::new()
+                .ok_or("cannot create `shaderc::Compiler`")?,
+            #[cfg(feature = "naga")]
+            validators: Mutex::new(Vec::new()),
+        };
+        Ok(out)
+    }
+
+    /// The compiler shared by all `jit_spirv!` invocations in the process. It's
+    /// created on the first call; if that fails, the next call tries again.
+    pub fn shared() -> Result<Arc<Compiler>, String> {
+        static SHARED_COMPILER: Mutex<Option<Arc<Compiler>>> = Mutex::new(None);
+        let mut shared = SHARED_COMPILER.lock()
+            .unwrap_or_else(PoisonError::into_inner);
+        if let Some(compiler) = shared.as_ref() {
+            return Ok(compiler.clone());
+        }
+        let compiler = Arc::new(Compiler::new()?);
+        *shared = Some(compiler.clone());
+        Ok(compiler)
+    }
+
+    #[cfg(feature = "naga")]
+    fn compile_naga(
+        &self,
+        src: &str,
+        opts: &CompileOptions,
+    ) -> Result<CompilationFeedback, String> {
+        use crate::dep::naga::valid::{Capabilities, ValidationFlags, Validator};
+        let validator = self.validators.lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .pop();
+        let mut validator = validator.unwrap_or_else(|| {
+            Validator::new(ValidationFlags::all(), Capabilities::all())
+        });
+        let out = crate::backends::naga::compile(&mut validator, src, opts);
+        self.validators.lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .push(validator);
+        out
+    }
+
+    /// Compile the shader source to SPIR-V. Backends are tried one by one
+    /// until one of them accepts the source. If all of the backends fail, the
+    /// error lists why each of them failed.
+    #[cfg_attr(not(any(feature = "shaderc", feature = "naga", feature = "spvasm")),
+        allow(unused_mut, unused_variables))]
+    pub fn compile(
+        &self,
+        src: &str,
+        opts: &CompileOptions,
+    ) -> Result<CompilationFeedback, String> {
+        let start = Instant::now();
+        let mut errors = Vec::new();
+        let mut out: Option<CompilationFeedback> = None;
+        #[cfg(feature = "spvasm")]
+        if out.is_none() {
+            out = accept_backend("spirq-spvasm",
+                crate::backends::spirq_spvasm::compile(src, opts), &mut errors);
+        }
+        #[cfg(feature = "shaderc")]
+        if out.is_none() {
+            out = accept_backend("shaderc",
+                crate::backends::shaderc::compile(&self.shaderc, src, opts), &mut errors);
+        }
+        #[cfg(feature = "naga")]
+        if out.is_none() {
+            out = accept_backend("naga", self.compile_naga(src, opts), &mut errors);
+        }
+        let mut feedback = out.ok_or_else(|| aggregate_errors(opts, errors))?;
+        // Backends may rename the entry point or infer the stage from the
+        // source, so take them from the compiled module.
+        let entry_points = entry_points(&feedback.spv);
+        let entry_point = match entry_points.iter().find(|(name, _)| *name == opts.entry) {
+            Some(entry_point) => Some(entry_point),
+            // The only entry point must be the requested one under another
+            // name. There is no telling which one it is if there are more.
+            None if entry_points.len() <= 1 => entry_points.first(),
+            None => {
+                let names = entry_points.iter()
+                    .map(|(name, _)| format!("\"{}\"", name))
+                    .collect::<Vec<_>>();
+                return Err(format!("entry point \"{}\" is not found in the compiled module, which has {}",
+                    opts.entry, names.join(", ")));
+            },
+        };
+        match entry_point {
+            Some((name, model)) => {
+                feedback.entry = name.clone();
+                feedback.stage = ShaderStage::from_execution_model(*model);
+            },
+            None => {
+                feedback.entry = opts.entry.clone();
+                feedback.stage = opts.stage;
+            },
+        }
+        feedback.compile_time = start.elapsed();
+        Ok(feedback)
+    }
+    /// Read the shader file at `path` and compile it. See
+    /// [`CompileOptions::source_file`] for how the options are completed.
+    pub fn compile_file<P: AsRef<Path>>(
+        &self,
+        path: P,
+        opts: &CompileOptions,
+    ) -> Result<CompilationFeedback, String> {
+        let (src, opts) = read_source_file(path.as_ref(), opts)?;
+        self.compile(&src, &opts)
+    }
+}
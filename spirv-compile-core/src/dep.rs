@@ -3,3 +3,6 @@ pub use ::shaderc;
 
 #[cfg(feature = "naga")]
 pub use ::naga;
+
+#[cfg(feature = "spvasm")]
+pub use ::spq_spvasm;
@@ -0,0 +1,108 @@
+//! Dependencies of compiled shaders for external build systems, either as a
+//! Makefile-style depfile understood by Make and Ninja, or as a JSON include
+//! graph:
+//!
+//! ```json
+//! {
+//!   "files": ["water.frag", "include/light.glsl"],
+//!   "includes": [
+//!     {"includer": "water.frag", "included": "include/light.glsl"}
+//!   ]
+//! }
+//! ```
+//!
+//! `files` lists the paths of the sources, the resource limit files and all
+//! the included files; `includes` lists every `#include` resolved in
+//! compilation, in the order they are resolved.
+use crate::CompilationFeedback;
+
+/// Dependencies of one or more compiled shaders.
+#[derive(Debug, Default, Clone)]
+pub struct DepGraph {
+    /// Paths of all the files the shaders depend on, without duplicates.
+    pub dep_paths: Vec<String>,
+    /// Pairs of the includer name and the included name.
+    pub includes: Vec<(String, String)>,
+}
+impl DepGraph {
+    pub fn new() -> Self {
+        Default::default()
+    }
+    /// Add the dependencies of a compiled shader.
+    pub fn add(&mut self, feedback: &CompilationFeedback) {
+        for dep_path in feedback.dep_paths.iter() {
+            if !self.dep_paths.contains(dep_path) {
+                self.dep_paths.push(dep_path.clone());
+            }
+        }
+        for include in feedback.includes.iter() {
+            let edge = (include.includer.clone(), include.resolved_name.clone());
+            if !self.includes.contains(&edge) {
+                self.includes.push(edge);
+            }
+        }
+    }
+
+    /// A Makefile rule of `target` depending on all the files.
+    pub fn to_depfile(&self, target: &str) -> String {
+        let mut out = format!("{}:", escape_make_path(target));
+        for dep_path in self.dep_paths.iter() {
+            out.push_str(" \\\n  ");
+            out.push_str(&escape_make_path(dep_path));
+        }
+        out.push('\n');
+        out
+    }
+    /// The include graph in JSON.
+    pub fn to_json(&self) -> String {
+        let files = self.dep_paths.iter()
+            .map(|x| json_str(x))
+            .collect::<Vec<_>>();
+        let includes = self.includes.iter()
+            .map(|(includer, included)| format!("    {{\"includer\": {}, \"included\": {}}}",
+                json_str(includer), json_str(included)))
+            .collect::<Vec<_>>();
+        let mut out = format!("{{\n  \"files\": [{}],\n  \"includes\": [", files.join(", "));
+        if !includes.is_empty() {
+            out.push('\n');
+            out.push_str(&includes.join(",\n"));
+            out.push_str("\n  ");
+        }
+        out.push_str("]\n}\n");
+        out
+    }
+}
+impl From<&CompilationFeedback> for DepGraph {
+    fn from(feedback: &CompilationFeedback) -> Self {
+        let mut out = DepGraph::new();
+        out.add(feedback);
+        out
+    }
+}
+
+/// Escape a path in a Makefile rule.
+fn escape_make_path(path: &str) -> String {
+    path.replace('\\', "/")
+        .replace(' ', "\\ ")
+        .replace(':', "\\:")
+        .replace('#', "\\#")
+        .replace('$', "$$")
+}
+
+fn json_str(x: &str) -> String {
+    let mut out = String::with_capacity(x.len() + 2);
+    out.push('"');
+    for c in x.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            c if (c as u32) < 0x20 => out.push_str(&format!("\\u{:04x}", c as u32)),
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+    out
+}
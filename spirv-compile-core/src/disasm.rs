@@ -0,0 +1,8 @@
+//! Runtime SPIR-V disassembly.
+
+/// Disassemble a SPIR-V module to SPIR-V assembly, with the header comments as
+/// `spirv-dis` prints them, so it can be assembled back bit-exactly with
+/// `SourceLanguage::Spvasm`.
+pub fn disassemble_spirv(spv: &[u32]) -> Result<String, String> {
+    crate::backends::spirq_spvasm::disassemble(spv)
+}
@@ -0,0 +1,275 @@
+//! Include resolution, e.g., from packed asset archives or in-memory overlays.
+use std::collections::HashMap;
+use std::fmt;
+use std::hash::{Hash, Hasher};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use crate::compiler::CompileOptions;
+
+/// Kind of the `#include` directive.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum IncludeType {
+    /// `#include "foo"`, relative to the including source.
+    Relative,
+    /// `#include <foo>`, searched in include directories.
+    Standard,
+}
+
+/// An included source.
+pub struct ResolvedInclude {
+    /// Canonical name of the included source. It's reported in diagnostics,
+    /// recorded in `CompilationFeedback::dep_paths`, and given back as the
+    /// includer name when the included source includes other sources.
+    pub name: String,
+    pub content: String,
+}
+
+/// FNV-1a. Unlike `DefaultHasher`, the hash values are stable across Rust
+/// releases so they can be stored on disk.
+pub struct StableHasher(u64);
+impl StableHasher {
+    pub fn new() -> Self {
+        StableHasher(0xcbf2_9ce4_8422_2325)
+    }
+}
+impl Default for StableHasher {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+impl Hasher for StableHasher {
+    fn write(&mut self, bytes: &[u8]) {
+        for byte in bytes {
+            self.0 ^= *byte as u64;
+            self.0 = self.0.wrapping_mul(0x0000_0100_0000_01b3);
+        }
+    }
+    fn finish(&self) -> u64 {
+        self.0
+    }
+}
+
+/// Stable hash of the content of a source.
+pub fn content_hash(content: &str) -> u64 {
+    let mut hasher = StableHasher::new();
+    hasher.write(content.as_bytes());
+    hasher.finish()
+}
+
+/// An `#include` resolved in compilation. It's recorded so the resolution can
+/// be checked again later, e.g., to tell whether a cached shader is outdated.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct IncludeRecord {
+    /// Name of the including source, as given to the resolver.
+    pub includer: String,
+    /// Name in the `#include` directive.
+    pub name: String,
+    pub ty: IncludeType,
+    /// Canonical name of the included source, i.e., `ResolvedInclude::name`.
+    pub resolved_name: String,
+    /// [`content_hash`] of the included source.
+    pub content_hash: u64,
+}
+impl IncludeRecord {
+    pub fn new(includer: &str, name: &str, ty: IncludeType, incl: &ResolvedInclude) -> Self {
+        IncludeRecord {
+            includer: includer.to_owned(),
+            name: name.to_owned(),
+            ty,
+            resolved_name: incl.name.clone(),
+            content_hash: content_hash(&incl.content),
+        }
+    }
+    /// Resolve the include again with the resolver and the include directories
+    /// in `opts`, and check that it's still resolved to the same source with
+    /// the same content.
+    pub fn is_up_to_date(&self, opts: &CompileOptions) -> bool {
+        let resolver: &dyn IncludeResolver = match &opts.resolver {
+            Some(resolver) => &*resolver.0,
+            None => &FsResolver,
+        };
+        match resolver.resolve(&self.name, self.ty, &self.includer, &opts.incl_dirs) {
+            Ok(incl) => {
+                incl.name == self.resolved_name &&
+                    content_hash(&incl.content) == self.content_hash
+            },
+            Err(_) => false,
+        }
+    }
+}
+
+/// Resolve `#include` directives in shader sources.
+pub trait IncludeResolver: Send + Sync {
+    /// Resolve the source `name` included by the source named `includer`.
+    /// `incl_dirs` are the include directories in the compile options.
+    fn resolve(
+        &self,
+        name: &str,
+        ty: IncludeType,
+        includer: &str,
+        incl_dirs: &[PathBuf],
+    ) -> Result<ResolvedInclude, String>;
+}
+
+/// Resolve includes from the filesystem. Relative includes are resolved
+/// against the directory of the includer, and standard includes are searched
+/// in the include directories in order.
+#[derive(Default)]
+pub struct FsResolver;
+impl IncludeResolver for FsResolver {
+    fn resolve(
+        &self,
+        name: &str,
+        ty: IncludeType,
+        includer: &str,
+        incl_dirs: &[PathBuf],
+    ) -> Result<ResolvedInclude, String> {
+        let path = match ty {
+            IncludeType::Relative => {
+                let cur_dir = Path::new(includer).parent()
+                    .ok_or("the shader source is not living in a filesystem, but attempts to include a relative path")?;
+                cur_dir.join(name)
+            },
+            IncludeType::Standard => {
+                incl_dirs.iter()
+                    .map(|incl_dir| incl_dir.join(name))
+                    .find(|path| path.exists())
+                    .ok_or(format!("cannot find \"{}\" in include directories", name))?
+            },
+        };
+        let path_lit = path.to_string_lossy().to_string();
+        let content = std::fs::read_to_string(path)
+            .map_err(|e| format!("cannot read from \"{}\": {}", path_lit, e))?;
+        Ok(ResolvedInclude { name: path_lit, content })
+    }
+}
+
+/// Join `/`-separated paths, resolving `.` and `..` components.
+fn join_virtual_path(dir: &str, name: &str) -> String {
+    let mut segs = Vec::new();
+    for seg in dir.split('/').chain(name.split('/')) {
+        match seg {
+            "" | "." => {},
+            ".." => { segs.pop(); },
+            _ => segs.push(seg),
+        }
+    }
+    segs.join("/")
+}
+
+/// Resolve includes from sources in memory, named by `/`-separated paths.
+/// Relative includes are resolved against the directory of the includer, and
+/// standard includes are looked up in the include directories, and then by
+/// the name itself.
+#[derive(Default)]
+pub struct MemoryResolver {
+    files: HashMap<String, String>,
+}
+impl MemoryResolver {
+    pub fn new() -> Self {
+        Default::default()
+    }
+    /// Add a source named `name`.
+    pub fn file<N: AsRef<str>, C: Into<String>>(mut self, name: N, content: C) -> Self {
+        self.insert(name, content);
+        self
+    }
+    /// Add or replace a source named `name`.
+    pub fn insert<N: AsRef<str>, C: Into<String>>(&mut self, name: N, content: C) {
+        self.files.insert(join_virtual_path("", name.as_ref()), content.into());
+    }
+}
+impl IncludeResolver for MemoryResolver {
+    fn resolve(
+        &self,
+        name: &str,
+        ty: IncludeType,
+        includer: &str,
+        incl_dirs: &[PathBuf],
+    ) -> Result<ResolvedInclude, String> {
+        let candidates = match ty {
+            IncludeType::Relative => {
+                let cur_dir = includer.rfind('/').map_or("", |i| &includer[..i]);
+                vec![join_virtual_path(cur_dir, name)]
+            },
+            IncludeType::Standard => {
+                incl_dirs.iter()
+                    .map(|incl_dir| join_virtual_path(&incl_dir.to_string_lossy(), name))
+                    .chain(std::iter::once(join_virtual_path("", name)))
+                    .collect()
+            },
+        };
+        candidates.into_iter()
+            .find_map(|path| {
+                let content = self.files.get(&path)?.clone();
+                Some(ResolvedInclude { name: path, content })
+            })
+            .ok_or(format!("cannot find \"{}\" in memory", name))
+    }
+}
+
+/// Try multiple resolvers in order, e.g., a mod overlay over the base game
+/// assets. The first one that resolves the include wins.
+#[derive(Default)]
+pub struct LayeredResolver {
+    layers: Vec<Arc<dyn IncludeResolver>>,
+}
+impl LayeredResolver {
+    pub fn new() -> Self {
+        Default::default()
+    }
+    /// Add a layer below the existing ones.
+    pub fn layer<R: IncludeResolver + 'static>(mut self, resolver: R) -> Self {
+        self.layers.push(Arc::new(resolver));
+        self
+    }
+    /// Add a shared layer below the existing ones.
+    pub fn shared_layer(mut self, resolver: Arc<dyn IncludeResolver>) -> Self {
+        self.layers.push(resolver);
+        self
+    }
+}
+impl IncludeResolver for LayeredResolver {
+    fn resolve(
+        &self,
+        name: &str,
+        ty: IncludeType,
+        includer: &str,
+        incl_dirs: &[PathBuf],
+    ) -> Result<ResolvedInclude, String> {
+        let mut errs = Vec::new();
+        for layer in self.layers.iter() {
+            match layer.resolve(name, ty, includer, incl_dirs) {
+                Ok(incl) => return Ok(incl),
+                Err(e) => errs.push(e),
+            }
+        }
+        if errs.is_empty() {
+            Err(format!("cannot find \"{}\" in any layer", name))
+        } else {
+            Err(errs.join("; "))
+        }
+    }
+}
+
+/// Resolver in `CompileOptions`. Compared by identity, so options with
+/// different resolver instances are never considered the same. The identity is
+/// not hashed though, so the hashes of options are stable across runs and can
+/// be used as on-disk keys; cached shaders are validated with the recorded
+/// `IncludeRecord`s instead.
+#[derive(Clone)]
+pub(crate) struct SharedResolver(pub(crate) Arc<dyn IncludeResolver>);
+impl fmt::Debug for SharedResolver {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("IncludeResolver")
+    }
+}
+impl PartialEq for SharedResolver {
+    fn eq(&self, other: &Self) -> bool {
+        Arc::as_ptr(&self.0) as *const () == Arc::as_ptr(&other.0) as *const ()
+    }
+}
+impl Eq for SharedResolver {}
+impl Hash for SharedResolver {
+    fn hash<H: Hasher>(&self, _: &mut H) {}
+}
@@ -0,0 +1,54 @@
+//! # spirv-compile-core
+//!
+//! Shader compilation internals shared by `inline-spirv` and `jit-spirv`. Both
+//! macro crates parse their options with `syntax::parse_compile_cfg` (with the
+//! `syntax` feature), so they accept exactly the same options, and compile
+//! with the same [`Compiler`] backends, either at build time or at runtime.
+//!
+//! You don't need to depend on this crate directly; `jit-spirv` re-exports
+//! everything you need.
+mod backends;
+pub mod compiler;
+pub mod dep;
+pub mod depgraph;
+#[cfg(feature = "spvasm")]
+pub mod disasm;
+pub mod include;
+#[cfg(feature = "shaderc")]
+pub mod limits;
+pub mod link;
+#[cfg(feature = "manifest")]
+pub mod manifest;
+#[cfg(feature = "syntax")]
+pub mod syntax;
+pub use compiler::{Compiler, CompileOptions, SourceLanguage, ShaderStage,
+    TargetEnv, OptimizationLevel, GlslProfile, ResourceKind};
+pub use depgraph::DepGraph;
+pub use include::{IncludeResolver, IncludeType, ResolvedInclude, FsResolver,
+    MemoryResolver, LayeredResolver, IncludeRecord};
+#[cfg(feature = "spvasm")]
+pub use disasm::disassemble_spirv;
+
+/// Result of a successful compilation.
+#[derive(Debug, Default, Clone)]
+pub struct CompilationFeedback {
+    /// SPIR-V binary words.
+    pub spv: Vec<u32>,
+    /// Paths of the source file, if given, the resource limit file, and all
+    /// the files it includes.
+    pub dep_paths: Vec<String>,
+    /// `#include`s resolved in compilation, in the order they are resolved.
+    /// The includer names and the resolved names are those in `dep_paths`, or
+    /// `<inline>` for inline sources without a path.
+    pub includes: Vec<IncludeRecord>,
+    /// Warning diagnostics, one message per line.
+    pub warnings: Vec<String>,
+    /// Name of the entry point in the SPIR-V module.
+    pub entry: String,
+    /// Stage of the entry point, either specified in the options or inferred
+    /// by the compiler, e.g., from `#pragma shader_stage(...)`.
+    pub stage: Option<ShaderStage>,
+    /// Time spent on compilation, or on loading from the cache if the shader
+    /// is found in a `jit_spirv::ShaderCache`.
+    pub compile_time: std::time::Duration,
+}
@@ -0,0 +1,127 @@
+//! glslang resource limits for the shaderc backend.
+use shaderc::Limit;
+
+/// Look up a resource limit by its name in glslang resource limit files.
+pub fn limit_from_name(name: &str) -> Option<Limit> {
+    let out = match name {
+        "MaxLights"                                 => Limit::MaxLights,
+        "MaxClipPlanes"                             => Limit::MaxClipPlanes,
+        "MaxTextureUnits"                           => Limit::MaxTextureUnits,
+        "MaxTextureCoords"                          => Limit::MaxTextureCoords,
+        "MaxVertexAttribs"                          => Limit::MaxVertexAttribs,
+        "MaxVertexUniformComponents"                => Limit::MaxVertexUniformComponents,
+        "MaxVaryingFloats"                          => Limit::MaxVaryingFloats,
+        "MaxVertexTextureImageUnits"                => Limit::MaxVertexTextureImageUnits,
+        "MaxCombinedTextureImageUnits"              => Limit::MaxCombinedTextureImageUnits,
+        "MaxTextureImageUnits"                      => Limit::MaxTextureImageUnits,
+        "MaxFragmentUniformComponents"              => Limit::MaxFragmentUniformComponents,
+        "MaxDrawBuffers"                            => Limit::MaxDrawBuffers,
+        "MaxVertexUniformVectors"                   => Limit::MaxVertexUniformVectors,
+        "MaxVaryingVectors"                         => Limit::MaxVaryingVectors,
+        "MaxFragmentUniformVectors"                 => Limit::MaxFragmentUniformVectors,
+        "MaxVertexOutputVectors"                    => Limit::MaxVertexOutputVectors,
+        "MaxFragmentInputVectors"                   => Limit::MaxFragmentInputVectors,
+        "MinProgramTexelOffset"                     => Limit::MinProgramTexelOffset,
+        "MaxProgramTexelOffset"                     => Limit::MaxProgramTexelOffset,
+        "MaxClipDistances"                          => Limit::MaxClipDistances,
+        "MaxComputeWorkGroupCountX"                 => Limit::MaxComputeWorkGroupCountX,
+        "MaxComputeWorkGroupCountY"                 => Limit::MaxComputeWorkGroupCountY,
+        "MaxComputeWorkGroupCountZ"                 => Limit::MaxComputeWorkGroupCountZ,
+        "MaxComputeWorkGroupSizeX"                  => Limit::MaxComputeWorkGroupSizeX,
+        "MaxComputeWorkGroupSizeY"                  => Limit::MaxComputeWorkGroupSizeY,
+        "MaxComputeWorkGroupSizeZ"                  => Limit::MaxComputeWorkGroupSizeZ,
+        "MaxComputeUniformComponents"               => Limit::MaxComputeUniformComponents,
+        "MaxComputeTextureImageUnits"               => Limit::MaxComputeTextureImageUnits,
+        "MaxComputeImageUniforms"                   => Limit::MaxComputeImageUniforms,
+        "MaxComputeAtomicCounters"                  => Limit::MaxComputeAtomicCounters,
+        "MaxComputeAtomicCounterBuffers"            => Limit::MaxComputeAtomicCounterBuffers,
+        "MaxVaryingComponents"                      => Limit::MaxVaryingComponents,
+        "MaxVertexOutputComponents"                 => Limit::MaxVertexOutputComponents,
+        "MaxGeometryInputComponents"                => Limit::MaxGeometryInputComponents,
+        "MaxGeometryOutputComponents"               => Limit::MaxGeometryOutputComponents,
+        "MaxFragmentInputComponents"                => Limit::MaxFragmentInputComponents,
+        "MaxImageUnits"                             => Limit::MaxImageUnits,
+        "MaxCombinedImageUnitsAndFragmentOutputs"   => Limit::MaxCombinedImageUnitsAndFragmentOutputs,
+        "MaxCombinedShaderOutputResources"          => Limit::MaxCombinedShaderOutputResources,
+        "MaxImageSamples"                           => Limit::MaxImageSamples,
+        "MaxVertexImageUniforms"                    => Limit::MaxVertexImageUniforms,
+        "MaxTessControlImageUniforms"               => Limit::MaxTessControlImageUniforms,
+        "MaxTessEvaluationImageUniforms"            => Limit::MaxTessEvaluationImageUniforms,
+        "MaxGeometryImageUniforms"                  => Limit::MaxGeometryImageUniforms,
+        "MaxFragmentImageUniforms"                  => Limit::MaxFragmentImageUniforms,
+        "MaxCombinedImageUniforms"                  => Limit::MaxCombinedImageUniforms,
+        "MaxGeometryTextureImageUnits"              => Limit::MaxGeometryTextureImageUnits,
+        "MaxGeometryOutputVertices"                 => Limit::MaxGeometryOutputVertices,
+        "MaxGeometryTotalOutputComponents"          => Limit::MaxGeometryTotalOutputComponents,
+        "MaxGeometryUniformComponents"              => Limit::MaxGeometryUniformComponents,
+        "MaxGeometryVaryingComponents"              => Limit::MaxGeometryVaryingComponents,
+        "MaxTessControlInputComponents"             => Limit::MaxTessControlInputComponents,
+        "MaxTessControlOutputComponents"            => Limit::MaxTessControlOutputComponents,
+        "MaxTessControlTextureImageUnits"           => Limit::MaxTessControlTextureImageUnits,
+        "MaxTessControlUniformComponents"           => Limit::MaxTessControlUniformComponents,
+        "MaxTessControlTotalOutputComponents"       => Limit::MaxTessControlTotalOutputComponents,
+        "MaxTessEvaluationInputComponents"          => Limit::MaxTessEvaluationInputComponents,
+        "MaxTessEvaluationOutputComponents"         => Limit::MaxTessEvaluationOutputComponents,
+        "MaxTessEvaluationTextureImageUnits"        => Limit::MaxTessEvaluationTextureImageUnits,
+        "MaxTessEvaluationUniformComponents"        => Limit::MaxTessEvaluationUniformComponents,
+        "MaxTessPatchComponents"                    => Limit::MaxTessPatchComponents,
+        "MaxPatchVertices"                          => Limit::MaxPatchVertices,
+        "MaxTessGenLevel"                           => Limit::MaxTessGenLevel,
+        "MaxViewports"                              => Limit::MaxViewports,
+        "MaxVertexAtomicCounters"                   => Limit::MaxVertexAtomicCounters,
+        "MaxTessControlAtomicCounters"              => Limit::MaxTessControlAtomicCounters,
+        "MaxTessEvaluationAtomicCounters"           => Limit::MaxTessEvaluationAtomicCounters,
+        "MaxGeometryAtomicCounters"                 => Limit::MaxGeometryAtomicCounters,
+        "MaxFragmentAtomicCounters"                 => Limit::MaxFragmentAtomicCounters,
+        "MaxCombinedAtomicCounters"                 => Limit::MaxCombinedAtomicCounters,
+        "MaxAtomicCounterBindings"                  => Limit::MaxAtomicCounterBindings,
+        "MaxVertexAtomicCounterBuffers"             => Limit::MaxVertexAtomicCounterBuffers,
+        "MaxTessControlAtomicCounterBuffers"        => Limit::MaxTessControlAtomicCounterBuffers,
+        "MaxTessEvaluationAtomicCounterBuffers"     => Limit::MaxTessEvaluationAtomicCounterBuffers,
+        "MaxGeometryAtomicCounterBuffers"           => Limit::MaxGeometryAtomicCounterBuffers,
+        "MaxFragmentAtomicCounterBuffers"           => Limit::MaxFragmentAtomicCounterBuffers,
+        "MaxCombinedAtomicCounterBuffers"           => Limit::MaxCombinedAtomicCounterBuffers,
+        "MaxAtomicCounterBufferSize"                => Limit::MaxAtomicCounterBufferSize,
+        "MaxTransformFeedbackBuffers"               => Limit::MaxTransformFeedbackBuffers,
+        "MaxTransformFeedbackInterleavedComponents" => Limit::MaxTransformFeedbackInterleavedComponents,
+        "MaxCullDistances"                          => Limit::MaxCullDistances,
+        "MaxCombinedClipAndCullDistances"           => Limit::MaxCombinedClipAndCullDistances,
+        "MaxSamples"                                => Limit::MaxSamples,
+        _ => return None,
+    };
+    Some(out)
+}
+
+/// Parse a glslang resource limit file, which has a limit name and its value
+/// on each line. Boolean limit flags in lower camel case (`whileLoops`, etc.)
+/// are not configurable in shaderc and are ignored.
+pub fn parse_limits(path: &str, text: &str) -> Result<Vec<(Limit, i32)>, String> {
+    let mut out = Vec::new();
+    for (i, line) in text.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with("//") || line.starts_with('#') {
+            continue;
+        }
+        let mut segs = line.split_whitespace();
+        let (name, value) = match (segs.next(), segs.next()) {
+            (Some(name), Some(value)) => (name, value),
+            _ => return Err(format!("{}:{}: expected a limit name and its value", path, i + 1)),
+        };
+        if name.starts_with(|c: char| c.is_ascii_lowercase()) {
+            continue;
+        }
+        let limit = limit_from_name(name)
+            .ok_or_else(|| format!("{}:{}: unknown limit `{}`", path, i + 1, name))?;
+        let value = value.parse::<i32>()
+            .map_err(|_| format!("{}:{}: invalid limit value `{}`", path, i + 1, value))?;
+        out.push((limit, value));
+    }
+    Ok(out)
+}
+
+/// Load limits from a glslang resource limit file.
+pub fn load_limits(path: &str) -> Result<Vec<(Limit, i32)>, String> {
+    let text = std::fs::read_to_string(path)
+        .map_err(|e| format!("cannot read from \"{}\": {}", path, e))?;
+    parse_limits(path, &text)
+}
@@ -0,0 +1,149 @@
+//! SPIR-V module linking, shared by `link_spirv!` at build time and
+//! `jit_spirv::link_spirv` at runtime.
+use std::path::PathBuf;
+use std::process::Command;
+use crate::compiler::{CompileOptions, TargetEnv};
+
+const OP_ENTRY_POINT: u32 = 15;
+const OP_EXECUTION_MODE: u32 = 16;
+const OP_EXECUTION_MODE_ID: u32 = 331;
+// Words in the SPIR-V module header.
+const HEADER_LEN: usize = 5;
+const SPIRV_MAGIC: u32 = 0x07230203;
+
+/// Target environment of the linked module given to `spirv-link`.
+fn target_env(opts: &CompileOptions) -> String {
+    match (opts.target, opts.spirv_version) {
+        (TargetEnv::OpenGL4_5, _) => "opengl4.5".to_owned(),
+        (_, Some((major, minor))) => format!("spv{}.{}", major, minor),
+        (TargetEnv::Vulkan1_0, None) => "vulkan1.0".to_owned(),
+        (TargetEnv::Vulkan1_1, None) => "vulkan1.1".to_owned(),
+        (TargetEnv::Vulkan1_2, None) => "vulkan1.2".to_owned(),
+        (TargetEnv::WebGpu, None) => "spv1.0".to_owned(),
+    }
+}
+
+fn make_temp_path(name: &str) -> PathBuf {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    static COUNTER: AtomicUsize = AtomicUsize::new(0);
+    let idx = COUNTER.fetch_add(1, Ordering::Relaxed);
+    std::env::temp_dir()
+        .join(format!("spirv-link-{}-{}-{}", std::process::id(), idx, name))
+}
+
+/// Decode a null-terminated literal string in SPIR-V words.
+fn decode_str(words: &[u32]) -> String {
+    let bytes = words.iter()
+        .flat_map(|x| x.to_le_bytes())
+        .take_while(|x| *x != 0)
+        .collect::<Vec<u8>>();
+    String::from_utf8_lossy(&bytes).into_owned()
+}
+
+/// Remove all entry points but the one named `entry` from a SPIR-V module. The
+/// functions of the dropped entry points remain as ordinary functions.
+pub fn select_entry_point(spv: &[u32], entry: &str) -> Result<Vec<u32>, String> {
+    if spv.len() < HEADER_LEN {
+        return Err("spirv is truncated".to_owned());
+    }
+    // Validate the instructions and find the function of the entry point
+    // first, so the second pass can index the operands safely.
+    let mut entry_func_id = None;
+    let mut i = HEADER_LEN;
+    while i < spv.len() {
+        let (len, op) = ((spv[i] >> 16) as usize, spv[i] & 0xffff);
+        if len == 0 || i + len > spv.len() {
+            return Err("spirv is malformed".to_owned());
+        }
+        match op {
+            // The execution model, the function ID and at least one word of
+            // the name.
+            OP_ENTRY_POINT if len < 4 => {
+                return Err("spirv has a truncated `OpEntryPoint`".to_owned());
+            },
+            OP_EXECUTION_MODE | OP_EXECUTION_MODE_ID if len < 3 => {
+                return Err("spirv has a truncated `OpExecutionMode`".to_owned());
+            },
+            OP_ENTRY_POINT if decode_str(&spv[i + 3..i + len]) == entry => {
+                entry_func_id = Some(spv[i + 2]);
+            },
+            _ => {},
+        }
+        i += len;
+    }
+    let entry_func_id = entry_func_id
+        .ok_or(format!("entry point \"{}\" is not found in any linked module", entry))?;
+
+    let mut out = spv[..HEADER_LEN].to_vec();
+    let mut i = HEADER_LEN;
+    while i < spv.len() {
+        let (len, op) = ((spv[i] >> 16) as usize, spv[i] & 0xffff);
+        let func_id = match op {
+            OP_ENTRY_POINT => Some(spv[i + 2]),
+            OP_EXECUTION_MODE | OP_EXECUTION_MODE_ID => Some(spv[i + 1]),
+            _ => None,
+        };
+        if func_id.is_none() || func_id == Some(entry_func_id) {
+            out.extend_from_slice(&spv[i..i + len]);
+        }
+        i += len;
+    }
+    Ok(out)
+}
+
+/// Link multiple SPIR-V modules into one with `spirv-link` from SPIRV-Tools,
+/// and keep only the entry point named by `entry` in `opts`. The modules are
+/// linked for the target environment and the SPIR-V version in `opts`. The
+/// linker executable is searched in `PATH` unless `SPIRV_LINK` gives the path
+/// to it.
+pub fn link_spirv(spvs: &[&[u32]], opts: &CompileOptions) -> Result<Vec<u32>, String> {
+    let linker = std::env::var("SPIRV_LINK")
+        .unwrap_or_else(|_| "spirv-link".to_owned());
+
+    let in_paths = spvs.iter()
+        .enumerate()
+        .map(|(i, spv)| {
+            let path = make_temp_path(&format!("{}.spv", i));
+            let bytes = spv.iter()
+                .flat_map(|x| x.to_le_bytes())
+                .collect::<Vec<u8>>();
+            std::fs::write(&path, bytes)
+                .map_err(|e| format!("cannot write linker input: {}", e))?;
+            Ok(path)
+        })
+        .collect::<Result<Vec<_>, String>>()?;
+    let out_path = make_temp_path("linked.spv");
+
+    let output = Command::new(&linker)
+        .arg("--target-env")
+        .arg(target_env(opts))
+        .args(&in_paths)
+        .arg("-o")
+        .arg(&out_path)
+        .output();
+    for path in in_paths.iter() {
+        let _ = std::fs::remove_file(path);
+    }
+    let output = output
+        .map_err(|e| format!("cannot launch `{}`: {}", linker, e))?;
+    if !output.status.success() {
+        let _ = std::fs::remove_file(&out_path);
+        return Err(format!("failed to link SPIR-V: {}",
+            String::from_utf8_lossy(&output.stderr)));
+    }
+
+    let bytes = std::fs::read(&out_path);
+    let _ = std::fs::remove_file(&out_path);
+    let bytes = bytes
+        .map_err(|e| format!("cannot read linker output: {}", e))?;
+    if bytes.len() & 3 != 0 {
+        return Err("linker produced misaligned spirv".to_owned());
+    }
+    let spv = bytes.chunks_exact(4)
+        .map(|x| u32::from_le_bytes([x[0], x[1], x[2], x[3]]))
+        .collect::<Vec<u32>>();
+    if spv.first() != Some(&SPIRV_MAGIC) {
+        return Err("linker produced invalid spirv".to_owned());
+    }
+    select_entry_point(&spv, &opts.entry)
+}
@@ -0,0 +1,324 @@
+//! Shader manifests, i.e., `shaders.toml` files declaring all the shaders of a
+//! crate with their options in one place:
+//!
+//! ```toml
+//! # Options of all the shaders.
+//! [defaults]
+//! target = "vulkan1_2"
+//! include_dirs = ["include"]
+//!
+//! [shaders.water]
+//! path = "water.hlsl"
+//! stage = "frag"
+//! entry = "main"
+//! defines = { USE_COLOR = true, LIGHT_COUNT = 4 }
+//!
+//! # Compile a shader for each combination of the definitions.
+//! [shaders.water.permutations]
+//! QUALITY = ["LOW", "HIGH"]
+//! ```
+//!
+//! A shader can have the following keys, and all of them but `path` and
+//! `permutations` can be given in `[defaults]`:
+//!
+//! - `path`: Path to the shader file, relative to the manifest;
+//! - `lang`: Source language, e.g., `"hlsl"`, as in the macro options;
+//! - `stage`: Shader stage, e.g., `"frag"`, as in the macro options;
+//! - `entry`: Name of the entry point;
+//! - `defines`: Definitions; `true` defines a macro without a value, and
+//!   `false` leaves it undefined;
+//! - `include_dirs`: Include directories, relative to the manifest;
+//! - `target`: Target environment, e.g., `"vulkan1_2"`, as in the macro
+//!   options;
+//! - `optimization`: `"none"`, `"min_size"` or `"max_perf"`;
+//! - `debug`: Whether to keep the debug information;
+//! - `auto_bind`: Whether to assign unbound resources automatically;
+//! - `permutations`: Lists of values of definitions; a shader is compiled for
+//!   each combination of them.
+//!
+//! The source language and the stage are inferred from the file extensions if
+//! they are not given. As with the `hlsl` macro option, HLSL shaders are
+//! optimized for performance unless `optimization` is given.
+use std::collections::BTreeMap;
+use std::ops::Range;
+use std::path::{Path, PathBuf};
+use serde::Deserialize;
+use toml::Spanned;
+use crate::compiler::{CompileOptions, OptimizationLevel, ShaderStage,
+    SourceLanguage, TargetEnv};
+
+#[derive(Deserialize)]
+#[serde(untagged, expecting = "a string, an integer or a boolean")]
+enum DefineValue {
+    Bool(bool),
+    Int(i64),
+    Str(String),
+}
+
+// Lists of values of definitions.
+type Permutations = BTreeMap<Spanned<String>, Vec<Spanned<DefineValue>>>;
+// Definitions of a permutation and the suffix of its name.
+type Permutation = (Vec<(String, String)>, String);
+
+#[derive(Deserialize, Default)]
+#[serde(deny_unknown_fields)]
+struct RawShader {
+    path: Option<Spanned<String>>,
+    lang: Option<Spanned<String>>,
+    stage: Option<Spanned<String>>,
+    entry: Option<String>,
+    #[serde(default)]
+    defines: BTreeMap<Spanned<String>, DefineValue>,
+    #[serde(default)]
+    include_dirs: Vec<String>,
+    target: Option<Spanned<String>>,
+    optimization: Option<Spanned<String>>,
+    debug: Option<bool>,
+    auto_bind: Option<bool>,
+    permutations: Option<Spanned<Permutations>>,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct RawManifest {
+    #[serde(default)]
+    defaults: RawShader,
+    #[serde(default)]
+    shaders: BTreeMap<Spanned<String>, RawShader>,
+}
+
+/// A shader declared in a manifest. Each permutation of a shader is a
+/// `ManifestShader` of its own.
+#[derive(Debug, Clone)]
+pub struct ManifestShader {
+    /// Name of the shader in upper snake case, with the definitions of the
+    /// permutation appended, e.g., `WATER_QUALITY_HIGH`.
+    pub name: String,
+    pub path: PathBuf,
+    pub opts: CompileOptions,
+    /// Where the shader is declared, e.g., `shaders.toml:10:10`, for
+    /// diagnostics.
+    pub location: String,
+}
+
+/// Manifest source and its path, to locate errors.
+struct Source<'a> {
+    path: &'a Path,
+    text: &'a str,
+}
+impl Source<'_> {
+    fn location(&self, span: Range<usize>) -> String {
+        let before = &self.text[..span.start.min(self.text.len())];
+        let line = before.matches('\n').count() + 1;
+        let col = before.rfind('\n').map_or(before, |i| &before[i + 1..]).chars().count() + 1;
+        format!("{}:{}:{}", self.path.display(), line, col)
+    }
+    fn error(&self, span: Range<usize>, msg: &str) -> String {
+        format!("{}: {}", self.location(span), msg)
+    }
+}
+
+fn is_ident(x: &str) -> bool {
+    x.starts_with(|c: char| c.is_ascii_alphabetic() || c == '_') &&
+        x.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
+fn upper_snake_case(x: &str) -> String {
+    x.chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c.to_ascii_uppercase() } else { '_' })
+        .collect()
+}
+
+/// Parse a `"name"` value with `f`, or report the expected values.
+fn parse_name<T>(
+    src: &Source,
+    value: &Option<Spanned<String>>,
+    f: fn(&str) -> Option<T>,
+    expected: &str,
+) -> Result<Option<T>, String> {
+    match value {
+        Some(value) => f(value.get_ref())
+            .map(Some)
+            .ok_or_else(|| src.error(value.span(), &format!("unknown {} `{}`", expected, value.get_ref()))),
+        None => Ok(None),
+    }
+}
+
+fn optim_lv_from_name(name: &str) -> Option<OptimizationLevel> {
+    let out = match name {
+        "none" => OptimizationLevel::None,
+        "min_size" => OptimizationLevel::MinSize,
+        "max_perf" => OptimizationLevel::MaxPerformance,
+        _ => return None,
+    };
+    Some(out)
+}
+
+/// Definitions of a shader, `None` for undefined ones so that shaders can
+/// undefine the defaults.
+fn collect_defines(
+    src: &Source,
+    defs: &BTreeMap<Spanned<String>, DefineValue>,
+    out: &mut BTreeMap<String, Option<Option<String>>>,
+) -> Result<(), String> {
+    for (k, v) in defs.iter() {
+        if !is_ident(k.get_ref()) {
+            return Err(src.error(k.span(), "definition name must be an identifier"));
+        }
+        let v = match v {
+            DefineValue::Bool(true) => Some(None),
+            DefineValue::Bool(false) => None,
+            DefineValue::Int(x) => Some(Some(x.to_string())),
+            DefineValue::Str(x) => Some(Some(x.clone())),
+        };
+        out.insert(k.get_ref().clone(), v);
+    }
+    Ok(())
+}
+
+/// Expand the permutations to the lists of the definitions and the name
+/// suffixes of each combination.
+fn expand_permutations(
+    src: &Source,
+    perms: &Permutations,
+) -> Result<Vec<Permutation>, String> {
+    let mut out = vec![(Vec::new(), String::new())];
+    for (k, values) in perms.iter() {
+        if !is_ident(k.get_ref()) {
+            return Err(src.error(k.span(), "definition name must be an identifier"));
+        }
+        if values.is_empty() {
+            return Err(src.error(k.span(), "permutation must have at least one value"));
+        }
+        let mut next = Vec::with_capacity(out.len() * values.len());
+        for (defs, suffix) in out.iter() {
+            for value in values.iter() {
+                let value_str = match value.get_ref() {
+                    DefineValue::Int(x) => x.to_string(),
+                    DefineValue::Str(x) => x.clone(),
+                    DefineValue::Bool(_) => {
+                        return Err(src.error(value.span(), "permutation value must be a \
+                            string or an integer"));
+                    },
+                };
+                let mut defs = defs.clone();
+                defs.push((k.get_ref().clone(), value_str.clone()));
+                let suffix = format!("{}_{}_{}", suffix, upper_snake_case(k.get_ref()),
+                    upper_snake_case(&value_str));
+                next.push((defs, suffix));
+            }
+        }
+        out = next;
+    }
+    Ok(out)
+}
+
+fn lower_shader(
+    src: &Source,
+    base_dir: &Path,
+    name: &Spanned<String>,
+    defaults: &RawShader,
+    shader: &RawShader,
+) -> Result<Vec<ManifestShader>, String> {
+    if !is_ident(name.get_ref()) {
+        return Err(src.error(name.span(), "shader name must be an identifier"));
+    }
+    let path = shader.path.as_ref()
+        .ok_or_else(|| src.error(name.span(), "shader has no `path`"))?;
+    let path = base_dir.join(path.get_ref());
+
+    let mut opts = CompileOptions::new();
+    for raw in [defaults, shader] {
+        if let Some(lang) = parse_name(src, &raw.lang, SourceLanguage::from_name, "source language")? {
+            opts = opts.lang(lang);
+        }
+        if let Some(stage) = parse_name(src, &raw.stage, ShaderStage::from_name, "shader stage")? {
+            opts = opts.stage(stage);
+        }
+        if let Some(entry) = &raw.entry {
+            opts = opts.entry(entry.clone());
+        }
+        opts = opts.include_dirs(raw.include_dirs.iter().map(|x| base_dir.join(x)));
+        if let Some(target) = parse_name(src, &raw.target, TargetEnv::from_name, "target environment")? {
+            opts = opts.target(target);
+        }
+        if let Some(optim_lv) = parse_name(src, &raw.optimization, optim_lv_from_name, "optimization level")? {
+            opts = opts.optimization(optim_lv);
+        }
+        if let Some(debug) = raw.debug {
+            opts = opts.debug(debug);
+        }
+        if let Some(auto_bind) = raw.auto_bind {
+            opts = opts.auto_bind(auto_bind);
+        }
+    }
+    opts = opts.source_file(&path);
+    // HLSL might be illegal if optimization is disabled, as with the `hlsl`
+    // macro option.
+    let has_optim_lv = defaults.optimization.is_some() || shader.optimization.is_some();
+    if opts.lang == Some(SourceLanguage::Hlsl) && !has_optim_lv {
+        opts = opts.optimization(OptimizationLevel::MaxPerformance);
+    }
+    let mut defs = BTreeMap::new();
+    collect_defines(src, &defaults.defines, &mut defs)?;
+    collect_defines(src, &shader.defines, &mut defs)?;
+
+    let perms = match &shader.permutations {
+        Some(perms) => expand_permutations(src, perms.get_ref())?,
+        None => vec![(Vec::new(), String::new())],
+    };
+    let out = perms.into_iter()
+        .map(|(perm_defs, suffix)| {
+            let mut defs = defs.clone();
+            defs.extend(perm_defs.into_iter().map(|(k, v)| (k, Some(Some(v)))));
+            let defs = defs.into_iter()
+                .filter_map(|(k, v)| Some((k, v?)));
+            ManifestShader {
+                name: format!("{}{}", upper_snake_case(name.get_ref()), suffix),
+                path: path.clone(),
+                opts: opts.clone().defines(defs),
+                location: src.location(name.span()),
+            }
+        })
+        .collect();
+    Ok(out)
+}
+
+/// Parse a manifest. `path` is the path to the manifest, reported in errors,
+/// and paths in the manifest are relative to its directory. Errors point at
+/// the line and the column of the invalid value.
+pub fn parse_manifest(text: &str, path: &Path) -> Result<Vec<ManifestShader>, String> {
+    let src = Source { path, text };
+    let manifest = toml::from_str::<RawManifest>(text)
+        .map_err(|e| match e.span() {
+            Some(span) => src.error(span, e.message()),
+            None => format!("{}: {}", path.display(), e.message()),
+        })?;
+    let RawManifest { defaults, shaders } = manifest;
+    if let Some(path) = &defaults.path {
+        return Err(src.error(path.span(), "`path` cannot be given in `[defaults]`"));
+    }
+    if let Some(perms) = &defaults.permutations {
+        return Err(src.error(perms.span(), "`permutations` cannot be given in `[defaults]`"));
+    }
+
+    let base_dir = path.parent().unwrap_or_else(|| Path::new(""));
+    let mut out: Vec<ManifestShader> = Vec::new();
+    for (name, shader) in shaders.iter() {
+        for shader in lower_shader(&src, base_dir, name, &defaults, shader)? {
+            if let Some(prev) = out.iter().find(|x| x.name == shader.name) {
+                return Err(src.error(name.span(), &format!("shader `{}` is already \
+                    declared at {}", shader.name, prev.location)));
+            }
+            out.push(shader);
+        }
+    }
+    Ok(out)
+}
+
+/// Read and parse the manifest at `path`. See [`parse_manifest`].
+pub fn load_manifest<P: AsRef<Path>>(path: P) -> Result<Vec<ManifestShader>, String> {
+    let path = path.as_ref();
+    let text = std::fs::read_to_string(path)
+        .map_err(|e| format!("cannot read from \"{}\": {}", path.display(), e))?;
+    parse_manifest(&text, path)
+}
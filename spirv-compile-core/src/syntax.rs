@@ -0,0 +1,439 @@
+//! Option grammar of the `inline-spirv` and `jit-spirv` macros.
+//!
+//! Both macros parse their options with [`parse_compile_cfg`], so they accept
+//! exactly the same options. Options that only make sense at build time or at
+//! runtime are still parsed by both, and are rejected with an error pointing
+//! at the option where they are not supported.
+use std::path::PathBuf;
+use syn::ext::IdentExt;
+use syn::parse::{ParseStream, Result as ParseResult};
+use syn::{Error, Expr, Ident, LitInt, LitStr, Token};
+use crate::compiler::{CompileOptions, GlslProfile, OptimizationLevel,
+    ResourceKind, ShaderStage, SourceLanguage, TargetEnv};
+
+/// Keywords of all the options following the shader source.
+pub const OPTION_KEYWORDS: &[&str] = &[
+    "path",
+    "glsl", "hlsl", "wgsl", "spvasm",
+    "vert", "tesc", "tese", "geom", "frag", "comp", "mesh", "task",
+    "rgen", "rint", "rahit", "rchit", "rmiss", "rcall",
+    "I", "D", "include_dirs", "defines",
+    "entry",
+    "min_size", "max_perf", "no_debug",
+    "vulkan", "vulkan1_0", "vulkan1_1", "vulkan1_2", "opengl", "opengl4_5", "webgpu",
+    "spirq1_0", "spirq1_1", "spirq1_2", "spirq1_3", "spirq1_4", "spirq1_5", "spirq1_6",
+    "auto_bind",
+    "shader_model", "hlsl_16bit_types", "hlsl_offsets", "hlsl_io_mapping",
+    "hlsl_functionality1", "hlsl_explicit_binding", "hlsl_explicit_binding_for_stage",
+    "glsl_version", "profile", "limit", "limits_file",
+    "binding_base", "binding_base_for_stage",
+    "generator", "bound",
+    "no_y_flip",
+    "dump", "out_version", "depfile", "include_graph",
+    "cache", "resolver", "crate",
+];
+
+/// Compilation options given to a macro. Values of include directories and
+/// definitions are kept as expressions; they are either string literals or
+/// expressions evaluated at runtime.
+pub struct ShaderCompilationConfig {
+    pub path: Option<String>,
+    pub lang: Option<SourceLanguage>,
+    pub stage: Option<ShaderStage>,
+    pub incl_dirs: Vec<Expr>,
+    pub defs: Vec<(String, Option<Expr>)>,
+    // Expressions of lists evaluated at runtime.
+    pub incl_dir_lists: Vec<Expr>,
+    pub def_lists: Vec<Expr>,
+    pub target: TargetEnv,
+    pub entry: String,
+    pub optim_lv: OptimizationLevel,
+    pub debug: bool,
+    pub auto_bind: bool,
+    // HLSL specific.
+    pub hlsl_shader_model: Option<(u32, u32)>,
+    pub hlsl_16bit_types: bool,
+    pub hlsl_offsets: bool,
+    pub hlsl_io_mapping: bool,
+    pub hlsl_functionality1: bool,
+    // Applied to all stages if the stage is `None`.
+    pub hlsl_explicit_bindings: Vec<(Option<ShaderStage>, String, u32, u32)>,
+    pub binding_bases: Vec<(Option<ShaderStage>, ResourceKind, u32)>,
+    // GLSL specific.
+    pub glsl_version: Option<u32>,
+    pub glsl_profile: Option<GlslProfile>,
+    pub limits: Vec<(String, i32)>,
+    pub limits_file: Option<String>,
+    // SPIR-V assembly specific.
+    pub spirv_version: Option<(u32, u32)>,
+    pub generator: Option<u32>,
+    pub bound: Option<u32>,
+    // Backend specific.
+    pub y_flip: bool,
+    // Build time only.
+    pub dump: Option<String>,
+    pub out_version: Option<String>,
+    pub depfile: Option<String>,
+    pub include_graph: Option<String>,
+    // Runtime only.
+    pub cache: Option<Expr>,
+    pub resolver: Option<Expr>,
+    // Path to the `jit_spirv` crate in the generated code.
+    pub crate_path: Option<syn::Path>,
+}
+impl Default for ShaderCompilationConfig {
+    fn default() -> Self {
+        ShaderCompilationConfig {
+            path: None,
+            lang: None,
+            stage: None,
+            incl_dirs: Vec::new(),
+            defs: Vec::new(),
+            incl_dir_lists: Vec::new(),
+            def_lists: Vec::new(),
+            target: TargetEnv::Vulkan1_0,
+            entry: "main".to_owned(),
+            optim_lv: OptimizationLevel::None,
+            debug: true,
+            auto_bind: false,
+            hlsl_shader_model: None,
+            hlsl_16bit_types: false,
+            hlsl_offsets: false,
+            hlsl_io_mapping: false,
+            hlsl_functionality1: false,
+            hlsl_explicit_bindings: Vec::new(),
+            binding_bases: Vec::new(),
+            glsl_version: None,
+            glsl_profile: None,
+            limits: Vec::new(),
+            limits_file: None,
+            spirv_version: None,
+            generator: None,
+            bound: None,
+            y_flip: true,
+            dump: None,
+            out_version: None,
+            depfile: None,
+            include_graph: None,
+            cache: None,
+            resolver: None,
+            crate_path: None,
+        }
+    }
+}
+
+#[inline]
+fn parse_str(input: &mut ParseStream) -> ParseResult<String> {
+    input.parse::<LitStr>()
+        .map(|x| x.value())
+}
+#[inline]
+fn parse_u32(input: &mut ParseStream) -> ParseResult<u32> {
+    input.parse::<LitInt>()?
+        .base10_parse::<u32>()
+}
+#[inline]
+fn parse_ident(input: &mut ParseStream) -> ParseResult<String> {
+    input.parse::<Ident>()
+        .map(|x| x.to_string())
+}
+
+fn parse_stage(input: ParseStream) -> ParseResult<ShaderStage> {
+    let k = input.parse::<Ident>()?;
+    ShaderStage::from_name(&k.to_string())
+        .ok_or_else(|| Error::new(k.span(), "unknown shader stage"))
+}
+
+/// Parse the comma-separated options following the shader source.
+pub fn parse_compile_cfg(
+    input: &mut ParseStream
+) -> ParseResult<ShaderCompilationConfig> {
+    let mut cfg = ShaderCompilationConfig::default();
+    while !input.is_empty() {
+        // Capture comma and collon; they are for readability.
+        input.parse::<Token![,]>()?;
+        // `crate` is a keyword.
+        let k = if let Ok(k) = input.call(Ident::parse_any) { k } else { break };
+        if let Some(stage) = ShaderStage::from_name(&k.to_string()) {
+            cfg.stage = Some(stage);
+            continue;
+        }
+        if let Some(target) = TargetEnv::from_name(&k.to_string()) {
+            // The target environment comes with its own SPIR-V version.
+            cfg.target = target;
+            cfg.spirv_version = None;
+            continue;
+        }
+        match &k.to_string() as &str {
+            "path" => {
+                input.parse::<Token![,]>()?;
+                cfg.path = Some(parse_str(input)?);
+            },
+
+            "glsl" => cfg.lang = Some(SourceLanguage::Glsl),
+            "hlsl" => {
+                cfg.lang = Some(SourceLanguage::Hlsl);
+                // HLSL might be illegal if optimization is disabled. Not sure,
+                // `glslangValidator` said this.
+                cfg.optim_lv = OptimizationLevel::MaxPerformance;
+            },
+            "wgsl" => cfg.lang = Some(SourceLanguage::Wgsl),
+            "spvasm" => cfg.lang = Some(SourceLanguage::Spvasm),
+
+            "I" => {
+                cfg.incl_dirs.push(input.parse::<Expr>()?)
+            },
+            "D" => {
+                let k = parse_ident(input)?;
+                let v = if input.parse::<Token![=]>().is_ok() {
+                    Some(input.parse::<Expr>()?)
+                } else { None };
+                cfg.defs.push((k, v));
+            },
+            "include_dirs" => {
+                input.parse::<Token![=]>()?;
+                cfg.incl_dir_lists.push(input.parse::<Expr>()?);
+            },
+            "defines" => {
+                input.parse::<Token![=]>()?;
+                cfg.def_lists.push(input.parse::<Expr>()?);
+            },
+
+            "entry" => {
+                if input.parse::<Token![=]>().is_ok() {
+                    cfg.entry = parse_str(input)?.to_owned();
+                }
+            }
+
+            "min_size" => cfg.optim_lv = OptimizationLevel::MinSize,
+            "max_perf" => cfg.optim_lv = OptimizationLevel::MaxPerformance,
+
+            "no_debug" => cfg.debug = false,
+
+            "spirq1_0" => cfg.spirv_version = Some((1, 0)),
+            "spirq1_1" => cfg.spirv_version = Some((1, 1)),
+            "spirq1_2" => cfg.spirv_version = Some((1, 2)),
+            "spirq1_3" => cfg.spirv_version = Some((1, 3)),
+            "spirq1_4" => cfg.spirv_version = Some((1, 4)),
+            "spirq1_5" => cfg.spirv_version = Some((1, 5)),
+            "spirq1_6" => cfg.spirv_version = Some((1, 6)),
+
+            "auto_bind" => cfg.auto_bind = true,
+
+            "shader_model" => {
+                input.parse::<Token![=]>()?;
+                let lit = input.parse::<LitStr>()?;
+                let sm = lit.value().split_once('.')
+                    .and_then(|(major, minor)| Some((major.parse().ok()?, minor.parse().ok()?)))
+                    .ok_or_else(|| Error::new(lit.span(), "shader model must be like \"6.2\""))?;
+                if !crate::compiler::is_hlsl_shader_model(sm) {
+                    return Err(Error::new(lit.span(), "unknown shader model; only 4.0, \
+                        4.1, 5.0, 5.1 and 6.0 to 6.8 are supported"));
+                }
+                cfg.hlsl_shader_model = Some(sm);
+            },
+            "hlsl_16bit_types" => cfg.hlsl_16bit_types = true,
+            "hlsl_offsets" => cfg.hlsl_offsets = true,
+            "hlsl_io_mapping" => cfg.hlsl_io_mapping = true,
+            "hlsl_functionality1" => cfg.hlsl_functionality1 = true,
+            "hlsl_explicit_binding" | "hlsl_explicit_binding_for_stage" => {
+                let content;
+                syn::parenthesized!(content in input);
+                let stage = if k == "hlsl_explicit_binding_for_stage" {
+                    let stage = parse_stage(&content)?;
+                    content.parse::<Token![,]>()?;
+                    Some(stage)
+                } else {
+                    None
+                };
+                let reg = content.parse::<LitStr>()?.value();
+                content.parse::<Token![,]>()?;
+                let set = content.parse::<LitInt>()?.base10_parse::<u32>()?;
+                content.parse::<Token![,]>()?;
+                let binding = content.parse::<LitInt>()?.base10_parse::<u32>()?;
+                cfg.hlsl_explicit_bindings.push((stage, reg, set, binding));
+            },
+            "glsl_version" => {
+                input.parse::<Token![=]>()?;
+                cfg.glsl_version = Some(input.parse::<LitInt>()?.base10_parse::<u32>()?);
+            },
+            "profile" => {
+                input.parse::<Token![=]>()?;
+                let profile = input.parse::<Ident>()?;
+                cfg.glsl_profile = match &profile.to_string() as &str {
+                    "core" => Some(GlslProfile::Core),
+                    "compatibility" => Some(GlslProfile::Compatibility),
+                    "es" => Some(GlslProfile::Es),
+                    _ => return Err(Error::new(profile.span(), "unknown glsl profile")),
+                };
+            },
+            "limit" => {
+                let content;
+                syn::parenthesized!(content in input);
+                while !content.is_empty() {
+                    let name = content.parse::<Ident>()?.to_string();
+                    content.parse::<Token![=]>()?;
+                    let is_neg = content.parse::<Token![-]>().is_ok();
+                    let value = content.parse::<LitInt>()?.base10_parse::<i32>()?;
+                    cfg.limits.push((name, if is_neg { -value } else { value }));
+                    if !content.is_empty() {
+                        content.parse::<Token![,]>()?;
+                    }
+                }
+            },
+            "limits_file" => {
+                input.parse::<Token![=]>()?;
+                cfg.limits_file = Some(parse_str(input)?);
+            },
+            "binding_base" | "binding_base_for_stage" => {
+                let content;
+                syn::parenthesized!(content in input);
+                let stage = if k == "binding_base_for_stage" {
+                    let stage = parse_stage(&content)?;
+                    content.parse::<Token![,]>()?;
+                    Some(stage)
+                } else {
+                    None
+                };
+                while !content.is_empty() {
+                    let res_kind_ident = content.parse::<Ident>()?;
+                    let res_kind = match &res_kind_ident.to_string() as &str {
+                        "image" => ResourceKind::Image,
+                        "sampler" => ResourceKind::Sampler,
+                        "texture" => ResourceKind::Texture,
+                        "buffer" => ResourceKind::Buffer,
+                        "storage_buffer" => ResourceKind::StorageBuffer,
+                        "uav" => ResourceKind::UnorderedAccessView,
+                        _ => return Err(Error::new(res_kind_ident.span(), "unknown resource kind")),
+                    };
+                    content.parse::<Token![=]>()?;
+                    let base = content.parse::<LitInt>()?.base10_parse::<u32>()?;
+                    cfg.binding_bases.push((stage, res_kind, base));
+                    if !content.is_empty() {
+                        content.parse::<Token![,]>()?;
+                    }
+                }
+            },
+
+            "generator" => {
+                input.parse::<Token![=]>()?;
+                cfg.generator = Some(parse_u32(input)?);
+            },
+            "bound" => {
+                input.parse::<Token![=]>()?;
+                cfg.bound = Some(parse_u32(input)?);
+            },
+
+            "no_y_flip" => cfg.y_flip = false,
+
+            "dump" => {
+                input.parse::<Token![=]>()?;
+                cfg.dump = Some(parse_str(input)?);
+            },
+            "out_version" => {
+                input.parse::<Token![=]>()?;
+                cfg.out_version = Some(parse_str(input)?);
+            },
+            "depfile" => {
+                input.parse::<Token![=]>()?;
+                cfg.depfile = Some(parse_str(input)?);
+            },
+            "include_graph" => {
+                input.parse::<Token![=]>()?;
+                cfg.include_graph = Some(parse_str(input)?);
+            },
+
+            "cache" => {
+                input.parse::<Token![=]>()?;
+                cfg.cache = Some(input.parse::<Expr>()?);
+            },
+            "resolver" => {
+                input.parse::<Token![=]>()?;
+                cfg.resolver = Some(input.parse::<Expr>()?);
+            },
+            "crate" => {
+                input.parse::<Token![=]>()?;
+                cfg.crate_path = Some(input.parse::<syn::Path>()?);
+            },
+
+            _ => return Err(Error::new(k.span(), "unsupported compilation parameter")),
+        }
+    }
+    Ok(cfg)
+}
+
+/// The value of a string literal expression.
+pub fn str_lit_value(expr: &Expr) -> Option<String> {
+    match expr {
+        Expr::Lit(syn::ExprLit { lit: syn::Lit::Str(x), .. }) => Some(x.value()),
+        _ => None,
+    }
+}
+fn expect_str_lit(expr: &Expr) -> ParseResult<String> {
+    str_lit_value(expr).ok_or_else(|| {
+        Error::new_spanned(expr, "expected a string literal; expressions are only \
+            evaluated at runtime with `jit_spirv!`")
+    })
+}
+fn runtime_only(expr: &Expr, name: &str) -> Error {
+    Error::new_spanned(expr, format!("`{}` is only supported at runtime with \
+        `jit_spirv!`", name))
+}
+
+impl ShaderCompilationConfig {
+    /// Lower the options to [`CompileOptions`] for compilation at build time.
+    /// Include directories and definitions must be string literals, and
+    /// runtime only options are rejected. Options specific to the macros,
+    /// e.g., `dump` and `out_version`, are left to the caller.
+    pub fn to_compile_options(&self) -> ParseResult<CompileOptions> {
+        if let Some(cache) = &self.cache {
+            return Err(runtime_only(cache, "cache"));
+        }
+        if let Some(resolver) = &self.resolver {
+            return Err(runtime_only(resolver, "resolver"));
+        }
+        if let Some(incl_dir_list) = self.incl_dir_lists.first() {
+            return Err(runtime_only(incl_dir_list, "include_dirs"));
+        }
+        if let Some(def_list) = self.def_lists.first() {
+            return Err(runtime_only(def_list, "defines"));
+        }
+        if let Some(crate_path) = &self.crate_path {
+            return Err(Error::new_spanned(crate_path, "`crate` is only supported \
+                by `jit_spirv!`"));
+        }
+
+        let mut opts = CompileOptions::new();
+        opts.path = self.path.clone();
+        opts.lang = self.lang;
+        opts.stage = self.stage;
+        for incl_dir in self.incl_dirs.iter() {
+            opts.incl_dirs.push(PathBuf::from(expect_str_lit(incl_dir)?));
+        }
+        for (k, v) in self.defs.iter() {
+            let v = v.as_ref().map(expect_str_lit).transpose()?;
+            opts.defs.push((k.clone(), v));
+        }
+        opts.target = self.target;
+        opts.entry = self.entry.clone();
+        opts.optim_lv = self.optim_lv;
+        opts.debug = self.debug;
+        opts.auto_bind = self.auto_bind;
+        opts.hlsl_shader_model = self.hlsl_shader_model;
+        opts.hlsl_16bit_types = self.hlsl_16bit_types;
+        opts.hlsl_offsets = self.hlsl_offsets;
+        opts.hlsl_io_mapping = self.hlsl_io_mapping;
+        opts.hlsl_functionality1 = self.hlsl_functionality1;
+        opts.hlsl_explicit_bindings = self.hlsl_explicit_bindings.clone();
+        opts.binding_bases = self.binding_bases.clone();
+        opts.glsl_version = self.glsl_version;
+        opts.glsl_profile = self.glsl_profile;
+        opts.limits = self.limits.clone();
+        opts.limits_file = self.limits_file.as_ref().map(PathBuf::from);
+        opts.spirv_version = self.spirv_version;
+        opts.generator = self.generator;
+        opts.bound = self.bound;
+        opts.y_flip = self.y_flip;
+        Ok(opts)
+    }
+}
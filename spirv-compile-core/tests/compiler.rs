@@ -0,0 +1,45 @@
+#![cfg(feature = "naga")]
+use spirv_compile_core::{CompileOptions, Compiler, ShaderStage, SourceLanguage};
+
+const FS_MAIN: &str = "@fragment fn fs_main() -> @location(0) vec4<f32> { return vec4<f32>(1.0); }";
+const VS_MAIN: &str = "@vertex fn vs_main() -> @builtin(position) vec4<f32> { return vec4<f32>(0.0); }";
+
+fn wgsl() -> CompileOptions {
+    CompileOptions::new().lang(SourceLanguage::Wgsl)
+}
+
+#[test]
+fn the_only_entry_point_is_taken_for_the_requested_one() {
+    let compiler = Compiler::new().unwrap();
+    // The default entry point name is `main`.
+    let feedback = compiler.compile(FS_MAIN, &wgsl()).unwrap();
+    assert_eq!(feedback.entry, "fs_main");
+    assert_eq!(feedback.stage, Some(ShaderStage::Fragment));
+
+    let src = format!("{}\n{}", VS_MAIN, FS_MAIN);
+    let feedback = compiler.compile(&src, &wgsl().entry("vs_main")).unwrap();
+    assert_eq!(feedback.entry, "vs_main");
+    assert_eq!(feedback.stage, Some(ShaderStage::Vertex));
+    let e = compiler.compile(&src, &wgsl()).unwrap_err();
+    assert!(e.contains("\"main\""), "{}", e);
+}
+
+/// Sources in no specific language are attempted by every backend, so the
+/// error tells why each of them failed.
+#[cfg(feature = "shaderc")]
+#[test]
+fn failures_of_every_attempting_backend_are_reported() {
+    let compiler = Compiler::new().unwrap();
+    let src = "void main() { undefined(); }";
+    let opts = CompileOptions::new().stage(ShaderStage::Fragment);
+    // A single backend attempting the source reports its error as is.
+    let shaderc_e = compiler.compile(src, &opts.clone().lang(SourceLanguage::Glsl)).unwrap_err();
+    let naga_e = compiler.compile(src, &opts.clone().lang(SourceLanguage::Wgsl)).unwrap_err();
+
+    let e = compiler.compile(src, &opts).unwrap_err();
+    assert!(e.starts_with("no compiler backend can compile the source:"), "{}", e);
+    for (name, backend_e) in [("shaderc", shaderc_e), ("naga", naga_e)] {
+        let expected = format!("\n{}: {}", name, backend_e.trim_end().replace('\n', "\n  "));
+        assert!(e.contains(&expected), "{}\n---\n{}", e, expected);
+    }
+}
@@ -0,0 +1,57 @@
+use spirv_compile_core::{CompilationFeedback, DepGraph, IncludeRecord, IncludeType};
+
+fn feedback(dep_paths: &[&str], includes: &[(&str, &str)]) -> CompilationFeedback {
+    let includes = includes.iter()
+        .map(|(includer, included)| IncludeRecord {
+            includer: includer.to_string(),
+            name: included.to_string(),
+            ty: IncludeType::Relative,
+            resolved_name: included.to_string(),
+            content_hash: 0,
+        })
+        .collect();
+    CompilationFeedback {
+        dep_paths: dep_paths.iter().map(|x| x.to_string()).collect(),
+        includes,
+        ..Default::default()
+    }
+}
+
+#[test]
+fn depfile_lists_every_dependency_once() {
+    let mut graph = DepGraph::from(&feedback(
+        &["water.frag", "include/light.glsl"],
+        &[("water.frag", "include/light.glsl")]));
+    graph.add(&feedback(
+        &["my sky.frag", "include/light.glsl"],
+        &[("my sky.frag", "include/light.glsl")]));
+    assert_eq!(graph.to_depfile("out/shaders.rs"), "out/shaders.rs: \\\n  \
+        water.frag \\\n  include/light.glsl \\\n  my\\ sky.frag\n");
+}
+
+#[test]
+fn special_characters_are_escaped_in_depfiles() {
+    let graph = DepGraph::from(&feedback(
+        &["C:\\shaders\\water.frag", "inc/#1 $light.glsl"], &[]));
+    assert_eq!(graph.to_depfile("out/a:b.spv"), "out/a\\:b.spv: \\\n  \
+        C\\:/shaders/water.frag \\\n  inc/\\#1\\ $$light.glsl\n");
+}
+
+#[test]
+fn include_graph_is_json() {
+    let graph = DepGraph::from(&feedback(
+        &["C:\\shaders\\water.frag", "inc/\"light\".glsl", "inc/common.glsl"],
+        &[
+            ("C:\\shaders\\water.frag", "inc/\"light\".glsl"),
+            ("inc/\"light\".glsl", "inc/common.glsl"),
+        ]));
+    assert_eq!(graph.to_json(), r#"{
+  "files": ["C:\\shaders\\water.frag", "inc/\"light\".glsl", "inc/common.glsl"],
+  "includes": [
+    {"includer": "C:\\shaders\\water.frag", "included": "inc/\"light\".glsl"},
+    {"includer": "inc/\"light\".glsl", "included": "inc/common.glsl"}
+  ]
+}
+"#);
+    assert_eq!(DepGraph::new().to_json(), "{\n  \"files\": [],\n  \"includes\": []\n}\n");
+}
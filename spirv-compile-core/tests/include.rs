@@ -0,0 +1,91 @@
+use std::path::{Path, PathBuf};
+use spirv_compile_core::{FsResolver, IncludeResolver, IncludeType, LayeredResolver,
+    MemoryResolver};
+
+fn shader_dir() -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR")).join("../tests/shaders")
+}
+
+#[test]
+fn standard_includes_are_only_searched_in_include_dirs() {
+    // Tests run in the crate root, so `Cargo.toml` is in the working directory.
+    let err = FsResolver.resolve("Cargo.toml", IncludeType::Standard, "<inline>", &[]);
+    assert!(err.is_err());
+    let incl_dirs = [PathBuf::from("tests"), PathBuf::from(".")];
+    let incl = FsResolver.resolve("Cargo.toml", IncludeType::Standard, "<inline>", &incl_dirs)
+        .unwrap();
+    assert_eq!(PathBuf::from(incl.name), PathBuf::from("./Cargo.toml"));
+    assert!(incl.content.contains("spirv-compile-core"));
+}
+
+#[test]
+fn relative_includes_are_resolved_next_to_the_includer() {
+    let includer = shader_dir().join("lit.frag");
+    let includer = includer.to_string_lossy();
+    let incl = FsResolver.resolve("light.glsl", IncludeType::Relative, &includer, &[])
+        .unwrap();
+    assert_eq!(PathBuf::from(incl.name), shader_dir().join("light.glsl"));
+    assert!(incl.content.contains("light"));
+    // Include directories are not searched for relative includes.
+    let incl_dirs = [PathBuf::from(".")];
+    assert!(FsResolver.resolve("Cargo.toml", IncludeType::Relative, &includer, &incl_dirs)
+        .is_err());
+}
+
+#[test]
+fn sources_in_memory_are_resolved_by_virtual_paths() {
+    let resolver = MemoryResolver::new()
+        .file("shaders/lit.frag", "")
+        .file("shaders/common/light.glsl", "vec3 light;")
+        .file("light.glsl", "vec4 light;");
+    let incl = resolver.resolve("common/light.glsl", IncludeType::Relative, "shaders/lit.frag", &[])
+        .unwrap();
+    assert_eq!(incl.name, "shaders/common/light.glsl");
+    assert_eq!(incl.content, "vec3 light;");
+    let incl = resolver.resolve("../../light.glsl", IncludeType::Relative, "shaders/common/light.glsl", &[])
+        .unwrap();
+    assert_eq!(incl.name, "light.glsl");
+    assert!(resolver.resolve("light.glsl", IncludeType::Relative, "shaders/lit.frag", &[])
+        .is_err());
+
+    // Standard includes are looked up in the include directories first, and
+    // then by the name itself.
+    let incl_dirs = [PathBuf::from("shaders/common")];
+    let incl = resolver.resolve("light.glsl", IncludeType::Standard, "shaders/lit.frag", &incl_dirs)
+        .unwrap();
+    assert_eq!(incl.name, "shaders/common/light.glsl");
+    let incl = resolver.resolve("light.glsl", IncludeType::Standard, "shaders/lit.frag", &[])
+        .unwrap();
+    assert_eq!(incl.name, "light.glsl");
+    let err = resolver.resolve("fog.glsl", IncludeType::Standard, "shaders/lit.frag", &incl_dirs)
+        .err().unwrap();
+    assert_eq!(err, "cannot find \"fog.glsl\" in memory");
+}
+
+#[test]
+fn upper_layers_take_precedence() {
+    let resolver = LayeredResolver::new()
+        .layer(MemoryResolver::new().file("light.glsl", "vec4 light;"))
+        .layer(MemoryResolver::new()
+            .file("light.glsl", "vec3 light;")
+            .file("fog.glsl", "float fog;"))
+        .layer(FsResolver);
+    let incl = resolver.resolve("light.glsl", IncludeType::Standard, "<inline>", &[]).unwrap();
+    assert_eq!(incl.content, "vec4 light;");
+    // Includes missing in the upper layers fall through to the lower ones.
+    let incl = resolver.resolve("fog.glsl", IncludeType::Standard, "<inline>", &[]).unwrap();
+    assert_eq!(incl.content, "float fog;");
+    let incl_dirs = [shader_dir()];
+    let incl = resolver.resolve("quad.wgsl", IncludeType::Standard, "<inline>", &incl_dirs)
+        .unwrap();
+    assert_eq!(PathBuf::from(incl.name), shader_dir().join("quad.wgsl"));
+
+    // The errors of every layer are reported.
+    let err = resolver.resolve("sky.glsl", IncludeType::Standard, "<inline>", &[]).err().unwrap();
+    assert_eq!(err, "cannot find \"sky.glsl\" in memory; cannot find \"sky.glsl\" in memory; \
+        cannot find \"sky.glsl\" in include directories");
+    let err = LayeredResolver::new()
+        .resolve("sky.glsl", IncludeType::Standard, "<inline>", &[])
+        .err().unwrap();
+    assert_eq!(err, "cannot find \"sky.glsl\" in any layer");
+}
@@ -0,0 +1,147 @@
+use spirv_compile_core::link::{link_spirv, select_entry_point};
+use spirv_compile_core::CompileOptions;
+
+const HEADER: [u32; 5] = [0x07230203, 0x00010000, 0, 16, 0];
+// Execution model `Fragment`.
+const FRAGMENT: u32 = 4;
+
+fn name_words(name: &str) -> Vec<u32> {
+    let mut bytes = name.as_bytes().to_vec();
+    bytes.resize((bytes.len() / 4 + 1) * 4, 0);
+    bytes.chunks_exact(4)
+        .map(|x| u32::from_le_bytes([x[0], x[1], x[2], x[3]]))
+        .collect()
+}
+fn entry_point(func_id: u32, name: &str) -> Vec<u32> {
+    let name = name_words(name);
+    let mut out = vec![((3 + name.len() as u32) << 16) | 15, FRAGMENT, func_id];
+    out.extend(name);
+    out
+}
+fn execution_mode(func_id: u32) -> Vec<u32> {
+    // `OriginUpperLeft`.
+    vec![(3 << 16) | 16, func_id, 7]
+}
+fn module(insts: &[Vec<u32>]) -> Vec<u32> {
+    HEADER.iter().copied().chain(insts.iter().flatten().copied()).collect()
+}
+
+#[test]
+fn only_the_selected_entry_point_is_kept() {
+    let spv = module(&[
+        entry_point(1, "main"),
+        entry_point(2, "other_main"),
+        execution_mode(1),
+        execution_mode(2),
+    ]);
+    let out = select_entry_point(&spv, "other_main").unwrap();
+    assert_eq!(out, module(&[entry_point(2, "other_main"), execution_mode(2)]));
+
+    let e = select_entry_point(&spv, "missing").unwrap_err();
+    assert!(e.contains("\"missing\""), "{}", e);
+}
+
+#[test]
+fn truncated_instructions_are_rejected() {
+    let cases = [
+        // `OpEntryPoint` without the function ID and the name.
+        module(&[vec![(2 << 16) | 15, FRAGMENT]]),
+        // `OpEntryPoint` without the name.
+        module(&[vec![(3 << 16) | 15, FRAGMENT, 1], entry_point(1, "main")]),
+        // `OpExecutionMode` without the mode.
+        module(&[entry_point(1, "main"), vec![(2 << 16) | 16, 1]]),
+        // Word count beyond the end of the module.
+        module(&[vec![(9 << 16) | 15, FRAGMENT, 1]]),
+        // Word count of zero.
+        module(&[vec![15]]),
+        HEADER[..3].to_vec(),
+    ];
+    for spv in cases.iter() {
+        assert!(select_entry_point(spv, "main").is_err(), "{:x?}", spv);
+    }
+}
+
+// Instructions of the modules to be linked.
+fn capabilities() -> Vec<u32> {
+    // `Shader`, `Linkage` and the logical GLSL450 memory model.
+    vec![(2 << 16) | 17, 1, (2 << 16) | 17, 5, (3 << 16) | 14, 0, 1]
+}
+fn linkage(func_id: u32, name: &str, is_export: bool) -> Vec<u32> {
+    let name = name_words(name);
+    // `OpDecorate` with `LinkageAttributes`.
+    let mut out = vec![((4 + name.len() as u32) << 16) | 71, func_id, 41];
+    out.extend(name);
+    out.push(if is_export { 0 } else { 1 });
+    out
+}
+// `%void = OpTypeVoid` and `%fn_ty = OpTypeFunction %void`.
+const VOID: u32 = 1;
+const FN_TY: u32 = 2;
+fn types() -> Vec<u32> {
+    vec![(2 << 16) | 19, VOID, (3 << 16) | 33, FN_TY, VOID]
+}
+fn function(func_id: u32, body: &[Vec<u32>]) -> Vec<u32> {
+    let mut out = vec![(5 << 16) | 54, VOID, func_id, 0, FN_TY];
+    if !body.is_empty() {
+        out.extend(body.iter().flatten());
+        // `OpReturn`.
+        out.push((1 << 16) | 253);
+    }
+    // `OpFunctionEnd`.
+    out.push((1 << 16) | 56);
+    out
+}
+fn label(id: u32) -> Vec<u32> {
+    vec![(2 << 16) | 248, id]
+}
+fn call(result_id: u32, func_id: u32) -> Vec<u32> {
+    vec![(4 << 16) | 57, VOID, result_id, func_id]
+}
+fn entry_point_names(spv: &[u32]) -> Vec<String> {
+    let mut out = Vec::new();
+    let mut i = HEADER.len();
+    while i < spv.len() {
+        let (len, op) = ((spv[i] >> 16) as usize, spv[i] & 0xffff);
+        if op == 15 {
+            let bytes = spv[i + 3..i + len].iter()
+                .flat_map(|x| x.to_le_bytes())
+                .take_while(|x| *x != 0)
+                .collect::<Vec<u8>>();
+            out.push(String::from_utf8(bytes).unwrap());
+        }
+        i += len;
+    }
+    out
+}
+
+#[test]
+#[ignore = "requires `spirv-link` from SPIRV-Tools"]
+fn modules_are_linked() {
+    // The shader calls `light` exported by the library.
+    let shader = module(&[
+        capabilities(),
+        entry_point(3, "main"),
+        entry_point(4, "other_main"),
+        execution_mode(3),
+        execution_mode(4),
+        linkage(5, "light", false),
+        types(),
+        function(5, &[]),
+        function(3, &[label(6), call(7, 5)]),
+        function(4, &[label(8)]),
+    ]);
+    let library = module(&[
+        capabilities(),
+        linkage(3, "light", true),
+        types(),
+        function(3, &[label(4)]),
+    ]);
+    let opts = CompileOptions::new().entry("main");
+    let spv = link_spirv(&[&shader, &library], &opts).unwrap();
+    assert_eq!(spv[0], HEADER[0]);
+    assert_eq!(entry_point_names(&spv), ["main"]);
+
+    // Imports must be resolved by one of the modules.
+    let e = link_spirv(&[&shader], &opts).unwrap_err();
+    assert!(e.contains("light"), "{}", e);
+}
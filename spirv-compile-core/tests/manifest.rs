@@ -0,0 +1,126 @@
+use std::path::Path;
+use spirv_compile_core::manifest::parse_manifest;
+use spirv_compile_core::{CompileOptions, OptimizationLevel, ShaderStage,
+    SourceLanguage, TargetEnv};
+
+fn parse_err(text: &str) -> String {
+    match parse_manifest(text, Path::new("assets/shaders.toml")) {
+        Ok(_) => panic!("manifest should be rejected:\n{}", text),
+        Err(e) => e,
+    }
+}
+
+#[test]
+fn shaders_are_lowered() {
+    let text = r#"
+[defaults]
+target = "vulkan1_2"
+include_dirs = ["include"]
+defines = { USE_COLOR = true, DESC_SET = 7 }
+
+[shaders.water]
+path = "water.hlsl"
+stage = "frag"
+entry = "ps_main"
+defines = { USE_COLOR = false, MAX_LIGHTS = "4" }
+
+[shaders.sky]
+path = "sky.vert"
+optimization = "min_size"
+debug = false
+"#;
+    let shaders = parse_manifest(text, Path::new("assets/shaders.toml")).unwrap();
+    let names = shaders.iter().map(|x| x.name.as_str()).collect::<Vec<_>>();
+    assert_eq!(names, ["SKY", "WATER"]);
+
+    let sky = &shaders[0];
+    assert_eq!(sky.path, Path::new("assets/sky.vert"));
+    assert_eq!(sky.location, "assets/shaders.toml:13:10");
+    let expected = CompileOptions::new()
+        .include_dir(Path::new("assets/include"))
+        .target(TargetEnv::Vulkan1_2)
+        .optimization(OptimizationLevel::MinSize)
+        .debug(false)
+        .source_file(Path::new("assets/sky.vert"))
+        .define("DESC_SET", Some("7"))
+        .define("USE_COLOR", None);
+    assert_eq!(sky.opts, expected);
+    assert_eq!(sky.opts.source_path(), Some("assets/sky.vert"));
+
+    let water = &shaders[1];
+    let expected = CompileOptions::new()
+        .stage(ShaderStage::Fragment)
+        .entry("ps_main")
+        .include_dir(Path::new("assets/include"))
+        .target(TargetEnv::Vulkan1_2)
+        .source_file(Path::new("assets/water.hlsl"))
+        // HLSL is optimized by default.
+        .optimization(OptimizationLevel::MaxPerformance)
+        .define("DESC_SET", Some("7"))
+        .define("MAX_LIGHTS", Some("4"));
+    assert_eq!(water.opts, expected);
+    assert_eq!(water.opts, expected.clone().lang(SourceLanguage::Hlsl));
+}
+
+#[test]
+fn permutations_are_expanded() {
+    let text = r#"
+[shaders.water]
+path = "water.frag"
+defines = { QUALITY = "LOW" }
+
+[shaders.water.permutations]
+QUALITY = ["low", "high"]
+LIGHTS = [1, 2]
+"#;
+    let shaders = parse_manifest(text, Path::new("shaders.toml")).unwrap();
+    let names = shaders.iter().map(|x| x.name.as_str()).collect::<Vec<_>>();
+    assert_eq!(names, [
+        "WATER_LIGHTS_1_QUALITY_LOW",
+        "WATER_LIGHTS_1_QUALITY_HIGH",
+        "WATER_LIGHTS_2_QUALITY_LOW",
+        "WATER_LIGHTS_2_QUALITY_HIGH",
+    ]);
+    let expected = CompileOptions::new()
+        .source_file(Path::new("water.frag"))
+        .define("LIGHTS", Some("2"))
+        .define("QUALITY", Some("high"));
+    assert_eq!(shaders[3].opts, expected);
+}
+
+#[test]
+fn errors_point_at_the_manifest_line() {
+    let cases = [
+        ("[shaders.a]\npath = \"a.frag\"\nstage = \"fragg\"\n",
+            "assets/shaders.toml:3:9: unknown shader stage `fragg`"),
+        ("[shaders.a]\npath = \"a.frag\"\ntarget = \"vulkan2\"\n",
+            "assets/shaders.toml:3:10: unknown target environment `vulkan2`"),
+        ("[shaders.a]\npath = \"a.frag\"\nlang = \"msl\"\n",
+            "assets/shaders.toml:3:8: unknown source language `msl`"),
+        ("[shaders.a]\npath = \"a.frag\"\noptimization = \"max\"\n",
+            "assets/shaders.toml:3:16: unknown optimization level `max`"),
+        ("[shaders.a]\npath = \"a.frag\"\npathh = \"a.frag\"\n",
+            "assets/shaders.toml:3:1: unknown field `pathh`"),
+        ("[shaders.a]\npath = \"a.frag\"\ndefines = { A = [1] }\n",
+            "assets/shaders.toml:3:17: a string, an integer or a boolean"),
+        ("[shaders.a]\npath = \"a.frag\"\ndefines = { \"A-B\" = 1 }\n",
+            "assets/shaders.toml:3:13: definition name must be an identifier"),
+        ("[shaders.a]\nstage = \"frag\"\n",
+            "assets/shaders.toml:1:10: shader has no `path`"),
+        ("[shaders.\"a-b\"]\npath = \"a.frag\"\n",
+            "assets/shaders.toml:1:10: shader name must be an identifier"),
+        ("[defaults]\npath = \"a.frag\"\n",
+            "assets/shaders.toml:2:8: `path` cannot be given in `[defaults]`"),
+        ("[shaders.a]\npath = \"a.frag\"\npermutations = { A = [true] }\n",
+            "assets/shaders.toml:3:23: permutation value must be a string or an integer"),
+        ("[shaders.a]\npath = \"a.frag\"\npermutations = { A = [] }\n",
+            "assets/shaders.toml:3:18: permutation must have at least one value"),
+        ("[shaders.a]\npath = \"a.frag\"\npermutations = { B = [1] }\n\n[shaders.a_b_1]\npath = \"a.frag\"\n",
+            "assets/shaders.toml:5:10: shader `A_B_1` is already declared at assets/shaders.toml:1:10"),
+    ];
+    for (text, expected) in cases {
+        let e = parse_err(text);
+        let (location, msg) = expected.split_once(": ").unwrap();
+        assert!(e.starts_with(location) && e.contains(msg), "{}\n{}", expected, e);
+    }
+}
@@ -0,0 +1,173 @@
+//! Both `inline_spirv!` and `jit_spirv!` parse their options with
+//! `parse_compile_cfg`, so these tests cover the options of both macros.
+use std::collections::BTreeSet;
+use syn::parse::{ParseStream, Parser};
+use spirv_compile_core::syntax::{parse_compile_cfg, ShaderCompilationConfig, OPTION_KEYWORDS};
+use spirv_compile_core::{CompileOptions, GlslProfile, OptimizationLevel,
+    ResourceKind, ShaderStage, SourceLanguage, TargetEnv};
+
+/// An example of each option.
+const EXAMPLES: &[(&str, &str)] = &[
+    ("path", r#"path, "shaders/a.glsl""#),
+    ("glsl", "glsl"),
+    ("hlsl", "hlsl"),
+    ("wgsl", "wgsl"),
+    ("spvasm", "spvasm"),
+    ("vert", "vert"),
+    ("tesc", "tesc"),
+    ("tese", "tese"),
+    ("geom", "geom"),
+    ("frag", "frag"),
+    ("comp", "comp"),
+    ("mesh", "mesh"),
+    ("task", "task"),
+    ("rgen", "rgen"),
+    ("rint", "rint"),
+    ("rahit", "rahit"),
+    ("rchit", "rchit"),
+    ("rmiss", "rmiss"),
+    ("rcall", "rcall"),
+    ("I", r#"I "include""#),
+    ("D", r#"D LIGHTMAP_COUNT="2""#),
+    ("include_dirs", "include_dirs=&dirs"),
+    ("defines", "defines=defs.iter()"),
+    ("entry", r#"entry="very_main""#),
+    ("min_size", "min_size"),
+    ("max_perf", "max_perf"),
+    ("no_debug", "no_debug"),
+    ("vulkan", "vulkan"),
+    ("vulkan1_0", "vulkan1_0"),
+    ("vulkan1_1", "vulkan1_1"),
+    ("vulkan1_2", "vulkan1_2"),
+    ("opengl", "opengl"),
+    ("opengl4_5", "opengl4_5"),
+    ("webgpu", "webgpu"),
+    ("spirq1_0", "spirq1_0"),
+    ("spirq1_1", "spirq1_1"),
+    ("spirq1_2", "spirq1_2"),
+    ("spirq1_3", "spirq1_3"),
+    ("spirq1_4", "spirq1_4"),
+    ("spirq1_5", "spirq1_5"),
+    ("spirq1_6", "spirq1_6"),
+    ("auto_bind", "auto_bind"),
+    ("shader_model", r#"shader_model="6.2""#),
+    ("hlsl_16bit_types", "hlsl_16bit_types"),
+    ("hlsl_offsets", "hlsl_offsets"),
+    ("hlsl_io_mapping", "hlsl_io_mapping"),
+    ("hlsl_functionality1", "hlsl_functionality1"),
+    ("hlsl_explicit_binding", r#"hlsl_explicit_binding("t0", 0, 1)"#),
+    ("hlsl_explicit_binding_for_stage", r#"hlsl_explicit_binding_for_stage(frag, "t0", 0, 1)"#),
+    ("glsl_version", "glsl_version=450"),
+    ("profile", "profile=core"),
+    ("limit", "limit(MaxClipDistances=8, MinProgramTexelOffset=-8)"),
+    ("limits_file", r#"limits_file="limits.conf""#),
+    ("binding_base", "binding_base(texture=0, sampler=16)"),
+    ("binding_base_for_stage", "binding_base_for_stage(vert, buffer=64)"),
+    ("generator", "generator=0x000d000a"),
+    ("bound", "bound=32"),
+    ("no_y_flip", "no_y_flip"),
+    ("dump", r#"dump="target/shader""#),
+    ("out_version", r#"out_version="2.1""#),
+    ("depfile", r#"depfile="target/shader.d""#),
+    ("include_graph", r#"include_graph="target/shader.json""#),
+    ("cache", "cache=&*CACHE"),
+    ("resolver", "resolver=resolver.clone()"),
+    ("crate", "crate=engine::jit_spirv"),
+];
+/// Options that are only supported by `jit_spirv!`.
+const JIT_ONLY: &[&str] = &["include_dirs", "defines", "cache", "resolver", "crate"];
+
+fn parse(options: &str) -> syn::Result<ShaderCompilationConfig> {
+    let parser = |mut input: ParseStream| parse_compile_cfg(&mut input);
+    parser.parse_str(&format!(", {}", options))
+}
+
+#[test]
+fn every_keyword_has_an_example() {
+    let keywords = OPTION_KEYWORDS.iter().copied().collect::<BTreeSet<_>>();
+    let examples = EXAMPLES.iter().map(|(k, _)| *k).collect::<BTreeSet<_>>();
+    assert_eq!(keywords, examples);
+    assert_eq!(keywords.len(), OPTION_KEYWORDS.len(), "duplicated keywords");
+}
+
+#[test]
+fn every_option_is_accepted() {
+    for (keyword, example) in EXAMPLES {
+        if let Err(e) = parse(example) {
+            panic!("option `{}` is rejected: {}", keyword, e);
+        }
+    }
+    let all = EXAMPLES.iter()
+        .map(|(_, example)| *example)
+        .collect::<Vec<_>>()
+        .join(", ");
+    parse(&all).unwrap();
+}
+
+#[test]
+fn unknown_options_are_rejected() {
+    for options in ["optimize", "glsl, vert, fast_math", "frag, profile=desktop",
+        "binding_base(constant=0)", "hlsl_explicit_binding_for_stage(pixel, \"t0\", 0, 1)",
+        "shader_model=\"6\"", "shader_model=\"7.0\"", "shader_model=\"5.2\""]
+    {
+        assert!(parse(options).is_err(), "`{}` is accepted", options);
+    }
+}
+
+#[test]
+fn build_time_options_are_lowered() {
+    for (keyword, example) in EXAMPLES {
+        let cfg = parse(example).unwrap();
+        let is_jit_only = JIT_ONLY.contains(keyword);
+        assert_eq!(cfg.to_compile_options().is_err(), is_jit_only,
+            "option `{}`", keyword);
+    }
+
+    let cfg = parse(r#"hlsl, frag, entry="ps_main", I "include", D USE_LIGHTMAP,
+        D LIGHTMAP_COUNT="2", vulkan1_2, min_size, no_debug, auto_bind,
+        shader_model="6.2", hlsl_16bit_types, hlsl_explicit_binding("t0", 0, 1),
+        binding_base_for_stage(vert, buffer=64), glsl_version=450,
+        profile=core, limit(MaxClipDistances=8), limits_file="limits.conf",
+        spirq1_5, generator=7, bound=32, no_y_flip"#).unwrap();
+    let expected = CompileOptions::new()
+        .lang(SourceLanguage::Hlsl)
+        .stage(ShaderStage::Fragment)
+        .entry("ps_main")
+        .include_dir("include")
+        .define("USE_LIGHTMAP", None)
+        .define("LIGHTMAP_COUNT", Some("2"))
+        .target(TargetEnv::Vulkan1_2)
+        .optimization(OptimizationLevel::MinSize)
+        .debug(false)
+        .auto_bind(true)
+        .hlsl_shader_model(6, 2)
+        .hlsl_16bit_types(true)
+        .hlsl_explicit_binding(None, "t0", 0, 1)
+        .binding_base(Some(ShaderStage::Vertex), ResourceKind::Buffer, 64)
+        .glsl_version(450)
+        .glsl_profile(GlslProfile::Core)
+        .limit("MaxClipDistances", 8)
+        .limits_file("limits.conf")
+        .spirv_version(1, 5)
+        .generator(7)
+        .bound(32)
+        .y_flip(false);
+    assert_eq!(cfg.to_compile_options().unwrap(), expected);
+}
+
+#[test]
+fn hlsl_is_optimized_by_default() {
+    let opts = parse("hlsl").unwrap().to_compile_options().unwrap();
+    let expected = CompileOptions::new()
+        .lang(SourceLanguage::Hlsl)
+        .optimization(OptimizationLevel::MaxPerformance);
+    assert_eq!(opts, expected);
+}
+
+#[test]
+fn runtime_values_are_rejected_at_build_time() {
+    for options in ["I asset_dir.join(\"shaders\")", "D LIGHTMAP_COUNT=lightmaps.len()"] {
+        let cfg = parse(options).unwrap();
+        assert!(cfg.to_compile_options().is_err(), "`{}` is lowered", options);
+    }
+}
@@ -0,0 +1,83 @@
+#![cfg(feature = "shaderc")]
+use spirv_compile_core::{CompileOptions, Compiler, ResourceKind, ShaderStage,
+    SourceLanguage};
+
+const HLSL_PS: &str = r#"
+    #if __SHADER_TARGET_MAJOR < 6
+    #error shader model 6.0 or above is required
+    #endif
+    float4 main() : SV_Target { return float4(1.0, 1.0, 1.0, 1.0); }
+"#;
+
+const GLSL_FS: &str = r#"
+    #version 450
+    layout(location = 0) out vec4 color;
+    uniform sampler2D albedo;
+    void main() { color = texture(albedo, vec2(0.0)); }
+"#;
+
+/// Binding numbers of all the `Binding` decorations in a SPIR-V module.
+fn bindings(spv: &[u32]) -> Vec<u32> {
+    const OP_DECORATE: u32 = 71;
+    const DECORATION_BINDING: u32 = 33;
+    let mut out = Vec::new();
+    // Skip the header.
+    let mut i = 5;
+    while i < spv.len() {
+        let (len, op) = ((spv[i] >> 16) as usize, spv[i] & 0xffff);
+        if op == OP_DECORATE && len == 4 && spv[i + 2] == DECORATION_BINDING {
+            out.push(spv[i + 3]);
+        }
+        i += len.max(1);
+    }
+    out
+}
+
+fn hlsl_ps() -> CompileOptions {
+    CompileOptions::new()
+        .lang(SourceLanguage::Hlsl)
+        .stage(ShaderStage::Fragment)
+}
+
+#[test]
+fn shader_models_are_checked() {
+    let compiler = Compiler::new().unwrap();
+    assert!(compiler.compile(HLSL_PS, &hlsl_ps().hlsl_shader_model(6, 0)).is_ok());
+    let e = compiler.compile(HLSL_PS, &hlsl_ps().hlsl_shader_model(5, 1)).unwrap_err();
+    assert!(e.contains("shader model 6.0 or above is required"), "{}", e);
+
+    let opts = hlsl_ps().hlsl_16bit_types(true);
+    let e = compiler.compile(HLSL_PS, &opts.clone().hlsl_shader_model(6, 0)).unwrap_err();
+    assert!(e.contains("16-bit types require shader model 6.2"), "{}", e);
+    assert!(compiler.compile(HLSL_PS, &opts.hlsl_shader_model(6, 2)).is_ok());
+}
+
+#[test]
+fn automatic_bindings_start_from_the_binding_base() {
+    let compiler = Compiler::new().unwrap();
+    let opts = CompileOptions::new()
+        .stage(ShaderStage::Fragment)
+        .auto_bind(true);
+    let spv = compiler.compile(GLSL_FS, &opts).unwrap().spv;
+    assert_eq!(bindings(&spv), [0]);
+
+    // Bases for other stages don't apply.
+    let opts = opts
+        .binding_base(None, ResourceKind::Texture, 4)
+        .binding_base(Some(ShaderStage::Vertex), ResourceKind::Texture, 8);
+    let spv = compiler.compile(GLSL_FS, &opts).unwrap().spv;
+    assert_eq!(bindings(&spv), [4]);
+}
+
+#[test]
+fn shaders_are_checked_against_the_limits() {
+    let src = "layout(local_size_x = 512) in; void main() {}";
+    let compiler = Compiler::new().unwrap();
+    // The source has no `#version` line.
+    let opts = CompileOptions::new()
+        .stage(ShaderStage::Compute)
+        .glsl_version(450);
+    assert!(compiler.compile(src, &opts).is_ok());
+    let e = compiler.compile(src, &opts.limit("MaxComputeWorkGroupSizeX", 256)).unwrap_err();
+    assert!(e.contains("MaxComputeWorkGroupSize"), "{}", e);
+}
@@ -0,0 +1,55 @@
+#![cfg(feature = "spvasm")]
+use std::path::{Path, PathBuf};
+use spirv_compile_core::{CompileOptions, Compiler, SourceLanguage};
+
+fn shader_dir() -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR")).join("../tests/shaders")
+}
+
+/// Values of all the `OpConstant`s in a SPIR-V module.
+fn constants(spv: &[u32]) -> Vec<u32> {
+    const OP_CONSTANT: u32 = 43;
+    let mut out = Vec::new();
+    // Skip the header.
+    let mut i = 5;
+    while i < spv.len() {
+        let (len, op) = ((spv[i] >> 16) as usize, spv[i] & 0xffff);
+        if op == OP_CONSTANT && len == 4 {
+            out.push(spv[i + 3]);
+        }
+        i += len.max(1);
+    }
+    out
+}
+
+#[test]
+fn includes_and_definitions_are_preprocessed() {
+    let opts = CompileOptions::new().define("SCALE", Some("7"));
+    let feedback = Compiler::new().unwrap()
+        .compile_file(shader_dir().join("fill.spvasm"), &opts)
+        .unwrap();
+    // One from the include, and the other from the definition.
+    assert_eq!(constants(&feedback.spv), [42, 7]);
+    assert!(feedback.dep_paths.iter().any(|x| x.ends_with("fill.spvasm")));
+    assert!(feedback.dep_paths.iter().any(|x| x.ends_with("constants.spvasm")),
+        "{:?}", feedback.dep_paths);
+}
+
+#[test]
+fn header_fields_override_the_header_comments() {
+    let src = "
+        ; Generator: Khronos SPIR-V Tools Assembler; 1
+        ; Bound: 16
+        OpCapability Shader
+        OpMemoryModel Logical GLSL450
+    ";
+    let compiler = Compiler::new().unwrap();
+    let opts = CompileOptions::new().lang(SourceLanguage::Spvasm);
+    let spv = compiler.compile(src, &opts).unwrap().spv;
+    assert_eq!(spv[2], (7 << 16) | 1);
+    assert_eq!(spv[3], 16);
+
+    let spv = compiler.compile(src, &opts.clone().generator(7).bound(32)).unwrap().spv;
+    assert_eq!(spv[2], 7);
+    assert_eq!(spv[3], 32);
+}
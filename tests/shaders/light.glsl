@@ -0,0 +1,3 @@
+float light(int count) {
+    return float(count) * 0.5;
+}
@@ -0,0 +1,6 @@
+#version 450
+#include "light.glsl"
+layout(location = 0) out vec4 color;
+void main() {
+    color = vec4(light(LIGHT_COUNT));
+}